@@ -0,0 +1,113 @@
+//! Looks at the shape of the schedule itself rather than the PTO balance.
+
+use crate::config::Vacation;
+use crate::sim::{self, HOURS_PER_DAY, Policy};
+use chrono::{Datelike, Month, NaiveDate};
+use std::collections::BTreeMap;
+
+/// A `--move` what-if: one trip rescheduled to a new start.
+#[derive(Debug, Clone)]
+pub struct Move {
+    /// Name or 1-based table position of the trip.
+    pub trip: String,
+    pub start: NaiveDate,
+}
+
+/// Parse a `--move` argument of the form `TRIP=YYYY-MM-DD`.
+pub fn parse_move(arg: &str) -> Result<Move, String> {
+    let (trip, start) = arg
+        .rsplit_once('=')
+        .ok_or_else(|| format!("expected TRIP=YYYY-MM-DD, got '{}'", arg))?;
+    let start = NaiveDate::parse_from_str(start.trim(), "%Y-%m-%d")
+        .map_err(|e| format!("invalid date '{}': {}", start, e))?;
+    Ok(Move {
+        trip: trip.trim().to_string(),
+        start,
+    })
+}
+
+/// The index of the trip named `trip` (ignoring case) or at 1-based
+/// position `trip`.
+pub fn find_trip(vacations: &[Vacation], trip: &str) -> Option<usize> {
+    match trip.parse::<usize>() {
+        Ok(position) => position.checked_sub(1).filter(|i| *i < vacations.len()),
+        Err(_) => vacations.iter().position(|v| {
+            v.name
+                .as_deref()
+                .unwrap_or("Unnamed")
+                .eq_ignore_ascii_case(trip)
+        }),
+    }
+}
+
+/// A stretch with no vacation, inclusive of both ends.
+#[derive(Debug, Clone, Copy)]
+pub struct Gap {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+impl Gap {
+    pub fn days(&self) -> i64 {
+        (self.to - self.from).num_days() + 1
+    }
+}
+
+/// Every stretch without time off, from `today` to the first trip and then
+/// between consecutive trips, longest first. `vacations` must be sorted.
+pub fn gaps(today: NaiveDate, vacations: &[Vacation]) -> Vec<Gap> {
+    let mut gaps = Vec::new();
+    let mut free_from = today;
+    for vacation in vacations {
+        if vacation.start > free_from {
+            gaps.push(Gap {
+                from: free_from,
+                to: vacation.start.pred_opt().unwrap(),
+            });
+        }
+        free_from = free_from.max(vacation.end.succ_opt().unwrap());
+    }
+    gaps.sort_by_key(|g| std::cmp::Reverse(g.days()));
+    gaps
+}
+
+/// Totals and extremes of the planned trips, in PTO days.
+#[derive(Debug, Clone)]
+pub struct Stats<'a> {
+    pub trips: usize,
+    pub total_days: f32,
+    pub total_hours: f32,
+    pub average_days: f32,
+    pub longest: (&'a Vacation, f32),
+    pub shortest: (&'a Vacation, f32),
+    /// The month the most trips start in, and how many do. Ties go to the
+    /// earlier month.
+    pub busiest_month: (Month, usize),
+}
+
+/// Describe the schedule; `None` when it's empty.
+pub fn stats<'a>(vacations: &'a [Vacation], policy: &Policy) -> Option<Stats<'a>> {
+    let days: Vec<(&Vacation, f32)> = vacations
+        .iter()
+        .map(|v| (v, sim::pto_days(v, policy)))
+        .collect();
+    let longest = *days.iter().max_by(|a, b| a.1.total_cmp(&b.1))?;
+    let shortest = *days.iter().min_by(|a, b| a.1.total_cmp(&b.1))?;
+    let total_days: f32 = days.iter().map(|(_, d)| d).sum();
+
+    let mut months: BTreeMap<u32, usize> = BTreeMap::new();
+    for vacation in vacations {
+        *months.entry(vacation.start.month()).or_default() += 1;
+    }
+    let (month, count) = months.into_iter().rev().max_by_key(|(_, count)| *count)?;
+
+    Some(Stats {
+        trips: vacations.len(),
+        total_days,
+        total_hours: total_days * HOURS_PER_DAY,
+        average_days: total_days / vacations.len() as f32,
+        longest,
+        shortest,
+        busiest_month: (Month::try_from(month as u8).ok()?, count),
+    })
+}
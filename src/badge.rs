@@ -0,0 +1,55 @@
+//! A shields.io-style status badge for `--badge`, as self-contained SVG.
+
+use crate::locale::Num;
+use crate::sim::Simulation;
+
+const GREEN: &str = "#4c1";
+const RED: &str = "#e05d44";
+/// Rough width of a character of 11px Verdana, the badge font.
+const CHAR_WIDTH: usize = 7;
+const PADDING: usize = 10;
+
+/// "PTO: on track" in green, or "PTO: N trips short" in red, with the final
+/// balance either way. Any trip that doesn't go ahead as planned is short.
+pub fn svg(simulation: &Simulation) -> String {
+    let short = simulation
+        .outcomes
+        .iter()
+        .filter(|o| !o.affordable())
+        .count();
+    let (status, color) = match (simulation.is_ok(), short) {
+        (true, _) => ("on track".to_string(), GREEN),
+        (false, 1) => ("1 trip short".to_string(), RED),
+        (false, n) => (format!("{} trips short", n), RED),
+    };
+    let message = format!("{} · {:.0}h", status, Num(simulation.final_balance));
+    let label = "PTO";
+
+    let label_width = label.chars().count() * CHAR_WIDTH + PADDING;
+    let message_width = message.chars().count() * CHAR_WIDTH + PADDING;
+    let width = label_width + message_width;
+    let label_x = label_width / 2;
+    let message_x = label_width + message_width / 2;
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {message}">
+  <title>{label}: {message}</title>
+  <linearGradient id="s" x2="0" y2="100%">
+    <stop offset="0" stop-color="#bbb" stop-opacity=".1"/>
+    <stop offset="1" stop-opacity=".1"/>
+  </linearGradient>
+  <clipPath id="r"><rect width="{width}" height="20" rx="3" fill="#fff"/></clipPath>
+  <g clip-path="url(#r)">
+    <rect width="{label_width}" height="20" fill="#555"/>
+    <rect x="{label_width}" width="{message_width}" height="20" fill="{color}"/>
+    <rect width="{width}" height="20" fill="url(#s)"/>
+  </g>
+  <g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11">
+    <text x="{label_x}" y="15" fill="#010101" fill-opacity=".3">{label}</text>
+    <text x="{label_x}" y="14">{label}</text>
+    <text x="{message_x}" y="15" fill="#010101" fill-opacity=".3">{message}</text>
+    <text x="{message_x}" y="14">{message}</text>
+  </g>
+</svg>
+"##
+    )
+}
@@ -0,0 +1,156 @@
+//! Month-grid view of the schedule, one `cal`-style month per month a
+//! vacation touches.
+
+use crate::sim::{Outcome, Policy};
+use chrono::{Datelike, NaiveDate, Weekday};
+use color_print::cformat;
+use std::collections::BTreeSet;
+
+/// How day markers are drawn.
+#[derive(Debug, Clone, Copy)]
+pub struct Style {
+    pub color: bool,
+    pub ascii: bool,
+    /// Marked in the grid: reversed in color, else behind a caret.
+    pub today: NaiveDate,
+}
+
+impl Style {
+    fn today_caret(self) -> &'static str {
+        if self.ascii { ">" } else { "▶" }
+    }
+}
+
+/// What a calendar day is, in order of precedence: a holiday or weekend
+/// inside a trip is still a free day. Recurring days off count as weekend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Day {
+    Holiday,
+    Weekend,
+    Vacation,
+    Shortfall,
+    Workday,
+}
+
+impl Day {
+    fn classify(date: NaiveDate, outcomes: &[Outcome], policy: &Policy) -> Self {
+        if policy.holidays.contains(&date) {
+            return Day::Holiday;
+        }
+        if policy.is_day_off(date) {
+            return Day::Weekend;
+        }
+        match outcomes
+            .iter()
+            .find(|o| o.vacation.start <= date && date <= o.vacation.end)
+        {
+            Some(outcome) if outcome.affordable() => Day::Vacation,
+            Some(_) => Day::Shortfall,
+            None => Day::Workday,
+        }
+    }
+
+    fn glyph(self, style: Style) -> &'static str {
+        match (self, style.ascii) {
+            (Day::Holiday, false) => "★",
+            (Day::Holiday, true) => "H",
+            (Day::Weekend, false) => "·",
+            (Day::Weekend, true) => ".",
+            (Day::Vacation, false) => "●",
+            (Day::Vacation, true) => "+",
+            (Day::Shortfall, false) => "✗",
+            (Day::Shortfall, true) => "!",
+            (Day::Workday, _) => " ",
+        }
+    }
+
+    fn paint(self, text: &str, style: Style) -> String {
+        if !style.color {
+            return text.to_string();
+        }
+        match self {
+            Day::Holiday => cformat!("<magenta>{}</magenta>", text),
+            Day::Weekend => cformat!("<dim>{}</dim>", text),
+            Day::Vacation => cformat!("<green>{}</green>", text),
+            Day::Shortfall => cformat!("<red>{}</red>", text),
+            Day::Workday => text.to_string(),
+        }
+    }
+}
+
+/// Every month containing a vacation day, followed by a legend.
+pub fn render(outcomes: &[Outcome], policy: &Policy, style: Style) -> String {
+    let months: BTreeSet<(i32, u32)> = outcomes
+        .iter()
+        .flat_map(|o| {
+            o.vacation
+                .start
+                .iter_days()
+                .take_while(|date| *date <= o.vacation.end)
+        })
+        .map(|date| (date.year(), date.month()))
+        .collect();
+
+    let mut out = String::new();
+    for (year, month) in months {
+        out += &month_grid(year, month, outcomes, policy, style);
+        out.push('\n');
+    }
+    let legend: Vec<String> = [
+        (Day::Vacation, "vacation"),
+        (Day::Shortfall, "unaffordable"),
+        (Day::Holiday, "holiday"),
+        (Day::Weekend, "weekend"),
+    ]
+    .into_iter()
+    .map(|(day, label)| day.paint(&format!("{} {}", day.glyph(style), label), style))
+    .chain([if style.color {
+        cformat!("<rev>today</rev>")
+    } else {
+        format!("{} today", style.today_caret())
+    }])
+    .collect();
+    out += &legend.join("  ");
+    out
+}
+
+fn month_grid(
+    year: i32,
+    month: u32,
+    outcomes: &[Outcome],
+    policy: &Policy,
+    style: Style,
+) -> String {
+    let first = NaiveDate::from_ymd_opt(year, month, 1).unwrap();
+    let title = format!("{:^27}", first.format("%B %Y").to_string());
+    let mut out = format!("{}\n", title.trim_end());
+    out += "Mo  Tu  We  Th  Fr  Sa  Su\n";
+    out += &"    ".repeat(first.weekday().num_days_from_monday() as usize);
+    for date in first.iter_days().take_while(|date| date.month() == month) {
+        let day = Day::classify(date, outcomes, policy);
+        let today = date == style.today;
+        let mut number = format!("{:>2}", date.day());
+        if today && style.color {
+            number = cformat!("<rev>{}</rev>", number);
+        } else if today {
+            // The caret takes the space before the day where there is one
+            if out.ends_with(' ') {
+                out.pop();
+            } else if number.starts_with(' ') {
+                number.remove(0);
+            }
+            out += style.today_caret();
+        }
+        out += &day.paint(&format!("{}{}", number, day.glyph(style)), style);
+        out += if date.weekday() == Weekday::Sun {
+            "\n"
+        } else {
+            " "
+        };
+    }
+    if !out.ends_with('\n') {
+        out.pop();
+        out.push('\n');
+    }
+    out
+}
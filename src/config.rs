@@ -0,0 +1,549 @@
+use crate::holidays::{HolidayEntry, HolidaySource};
+use crate::sim::{
+    AccrualFrequency, AccrualSource, BalanceReset, CapOverflowMode, CashOut, CategoryRules,
+    Clawback, DateRange, PartialHolidayRule, RoundingMode, StartingToday, Transfer, WorkPeriod,
+};
+use chrono::{Datelike, NaiveDate, Weekday};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Config {
+    pub pto_hours_per_week: Option<f32>,
+    pub annual_hours: Option<f32>,
+    pub pto_bank: Option<f32>,
+    /// Hourly accrual: hours worked each weekly pay period, overtime included
+    pub pay_period_hours: Option<f32>,
+    /// Hourly accrual: PTO hours earned per hour worked, e.g. `0.0385` for 1 per 26
+    pub accrual_per_hour_worked: Option<f32>,
+    /// Hourly accrual: most worked hours per period that earn PTO, e.g. `40`
+    pub accrual_hours_cap: Option<f32>,
+    pub holidays: Vec<HolidayEntry>,
+    /// How a holiday with a `fraction` combines with a vacation day:
+    /// `subtract` (the default) takes the share off from the day's charge,
+    /// `scale` multiplies the charge by the share worked, `free` charges
+    /// nothing. Weekends and whole holidays always cost nothing first.
+    #[serde(default)]
+    pub partial_holiday_rule: PartialHolidayRule,
+    /// Accruals on top of the weekly rate, e.g.
+    /// `[{ name = "wellness", hours = 1, frequency = "weekly" }]`. Frequency
+    /// is `weekly` (the default), `monthly`, `quarterly` or `yearly`.
+    #[serde(default)]
+    pub accrual_sources: Vec<AccrualSource>,
+    /// When the weekly rate is credited: `weekly` (the default), all of it on
+    /// Sundays, or `perBusinessDay`, a fifth of it each working day that
+    /// isn't a holiday. Sources may also use `perBusinessDay`, crediting
+    /// their `hours` each business day.
+    #[serde(default)]
+    pub accrual_frequency: AccrualFrequency,
+    /// Weekdays off every week on top of the weekend, e.g. `["Wed"]` for a
+    /// four-day week. They cost no PTO, like Saturday and Sunday; accrual
+    /// still lands as `accrualFrequency` says.
+    #[serde(default)]
+    pub recurring_days_off: Vec<Weekday>,
+    /// Per-category day counting, e.g. `categories.sick = { workdays = ["Mon", ..., "Sun"], holidays = [] }`
+    #[serde(default)]
+    pub categories: BTreeMap<String, Category>,
+    /// Opening balances of additional banks, e.g. `banks = { sick = 16 }`
+    #[serde(default)]
+    pub banks: BTreeMap<String, f32>,
+    #[serde(default)]
+    pub transfers: Vec<Transfer>,
+    #[serde(default)]
+    pub allow_negative_transfers: bool,
+    /// Most hours that may carry into the next year
+    pub carryover_cap: Option<f32>,
+    /// Part-time periods, e.g. `[{ start = 2027-01-01, end = 2027-06-30, fraction = 0.5 }]`
+    #[serde(default)]
+    pub work_schedule: Vec<WorkPeriod>,
+    /// Periods with no accrual, e.g. `[{ start = 2027-01-01, end = 2027-12-31 }]`
+    #[serde(default)]
+    pub no_accrual_ranges: Vec<DateRange>,
+    /// One-off PTO clawbacks, e.g. `[{ date = 2026-03-01, hours = 8, reason = "over-grant" }]`
+    #[serde(default)]
+    pub deductions: Vec<Clawback>,
+    /// Dates the balance is replaced outright, e.g.
+    /// `[{ date = 2027-01-01, balance = 40, reason = "new PTO plan" }]`;
+    /// `balance` defaults to 0
+    #[serde(default)]
+    pub reset_events: Vec<BalanceReset>,
+    /// When a vacation starts on an accrual day, credit the accrual first.
+    /// Off by default: the vacation is deducted, then the accrual credited.
+    #[serde(default)]
+    pub accrual_before_deduction: bool,
+    /// A vacation starting today is `"future"` (the default), deducted from
+    /// the bank in full, or `"inProgress"`, already taken out of it
+    #[serde(default)]
+    pub starting_today: StartingToday,
+    /// Credit an accrual landing today. Off by default: the bank is taken to
+    /// include it, and a trip starting today pays from the bank as it is.
+    #[serde(default)]
+    pub accrue_today: bool,
+    /// Highest balance accrual can reach
+    pub accrual_cap: Option<f32>,
+    /// What happens to accrual over the cap: `Drop` (default) or `Queue`
+    #[serde(default)]
+    pub cap_overflow_mode: CapOverflowMode,
+    /// Round each accrual credit to a multiple of this many hours, as
+    /// payroll does, e.g. `0.01`
+    pub accrual_rounding: Option<f32>,
+    /// Direction of `accrualRounding`: `Floor`, `Ceil` or `Nearest`
+    /// (default). Rounding compounds over every credit, so the mode can move
+    /// the year-end balance by an hour or more.
+    #[serde(default)]
+    pub accrual_rounding_mode: RoundingMode,
+    /// Hours granted at once each year, on top of any weekly accrual
+    pub annual_grant: Option<f32>,
+    /// When `annualGrant` lands: `"jan1"` (the default) or `"anniversary"`
+    /// of `hireDate`. Anniversary grants also move the carryover cap to the
+    /// anniversary.
+    #[serde(default)]
+    pub annual_grant_on: GrantDay,
+    /// First day of employment, for `probationDays`
+    #[serde(default, with = "toml_datetime_compat")]
+    pub hire_date: Option<NaiveDate>,
+    /// Days after `hireDate` during which nothing accrues
+    pub probation_days: Option<u32>,
+    /// Credit what probation would have accrued in one lump when it ends,
+    /// instead of losing it
+    #[serde(default)]
+    pub retroactive_after_probation: bool,
+    /// Last day of employment; accrual stops and later trips are out of range
+    #[serde(default, with = "toml_datetime_compat")]
+    pub employment_end: Option<NaiveDate>,
+    /// Balance above which year-end audits flag the account
+    pub audit_threshold: Option<f32>,
+    /// Longest stretch without a vacation before `--detect-gaps` warns
+    pub max_gap_days: Option<i64>,
+    /// Flat number of PTO days granted per year, to compare planned usage to
+    pub annual_allowance: Option<f32>,
+    /// Pay out hours above a threshold, e.g.
+    /// `cashOut = { threshold = 80, frequency = "yearly", hourlyRate = 40 }`
+    pub cash_out: Option<CashOut>,
+    /// Whether vacation ends are the last day off (`true`, the default) or
+    /// the first day back, as in half-open calendar exports
+    pub end_inclusive: Option<bool>,
+    /// Pay per hour, to value a `--payout-on` payout and unpaid leave
+    pub hourly_rate: Option<f32>,
+    /// Most hours paid out on leaving; the rest of the balance is lost
+    pub max_payout_hours: Option<f32>,
+    /// Most PTO days a single trip may use before it would be rejected
+    pub max_pto_days_per_trip: Option<f32>,
+    /// How many years ahead the simulation may project; trips further out
+    /// are dropped with a note. Defaults to 10
+    #[serde(default = "default_max_horizon_years")]
+    pub max_horizon_years: u32,
+}
+
+fn default_max_horizon_years() -> u32 {
+    10
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum GrantDay {
+    #[default]
+    Jan1,
+    Anniversary,
+}
+
+/// Overrides for vacations with a given `category`. `workdays` replaces
+/// Monday to Friday and `recurringDaysOff`; `holidays` replaces the global
+/// holiday list, so `[]` means holidays cost PTO too.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Category {
+    pub workdays: Option<Vec<Weekday>>,
+    pub holidays: Option<Vec<HolidayEntry>>,
+}
+
+impl Category {
+    pub fn into_rules(self) -> CategoryRules {
+        CategoryRules {
+            workdays: self.workdays,
+            holidays: self.holidays.map(|holidays| {
+                holidays
+                    .into_iter()
+                    .map(|h| h.into_holiday(HolidaySource::Config).date)
+                    .collect()
+            }),
+        }
+    }
+}
+
+impl Config {
+    /// The weekly accrual earned from hourly work, if configured: worked
+    /// hours up to `accrualHoursCap`, times `accrualPerHourWorked`.
+    pub fn hourly_accrual(&self) -> Result<Option<f32>, String> {
+        let (hours, rate) = match (self.pay_period_hours, self.accrual_per_hour_worked) {
+            (Some(hours), Some(rate)) => (hours, rate),
+            (None, None) if self.accrual_hours_cap.is_none() => return Ok(None),
+            _ => {
+                return Err(
+                    "payPeriodHours and accrualPerHourWorked must be set together".to_string(),
+                );
+            }
+        };
+        if hours < 0.0 {
+            return Err("payPeriodHours must not be negative".to_string());
+        }
+        if rate <= 0.0 {
+            return Err("accrualPerHourWorked must be positive".to_string());
+        }
+        match self.accrual_hours_cap {
+            Some(cap) if cap <= 0.0 => Err("accrualHoursCap must be positive".to_string()),
+            Some(cap) => Ok(Some(hours.min(cap) * rate)),
+            None => Ok(Some(hours * rate)),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Schedule {
+    pub vacations: Vec<Vacation>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Vacation {
+    /// Left out for a `flexibleLength` trip, which vacay dates itself.
+    #[serde(default = "unscheduled", with = "toml_datetime_compat")]
+    pub start: NaiveDate,
+    #[serde(default = "unscheduled", with = "toml_datetime_compat")]
+    pub end: NaiveDate,
+    pub name: Option<String>,
+    /// Label for grouping output, e.g. `"sick"` or `"vacation"`. Every
+    /// category is paid from the same PTO bank.
+    pub category: Option<String>,
+    /// Emoji shown before the name with `--icons`, e.g. `"🏖️"`
+    pub icon: Option<String>,
+    /// Free-form labels; some pick an icon when `icon` isn't set, e.g.
+    /// `["beach"]` or `["conference"]`
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Trip prep checklist, e.g. `booked = { flights = true, hotel = false }`
+    pub booked: Option<Booked>,
+    /// Share of a full PTO day each day costs, e.g. `0.5` for half days.
+    /// With `fractionAppliesTo = "all"` (the default) every PTO day in the
+    /// range is scaled; `"start"` or `"end"` scales only the first or last
+    /// PTO day and charges the rest in full.
+    pub fraction: Option<f32>,
+    #[serde(default)]
+    pub fraction_applies_to: FractionScope,
+    /// Take only this weekday off, every week from `start` to `end`, e.g.
+    /// `"Fri"` for summer Fridays. Each day is its own one-day trip.
+    pub recur_weekly: Option<Weekday>,
+    /// Length in PTO days of a trip with no fixed dates; it's placed on the
+    /// earliest start the balance can cover, e.g. `flexibleLength = 5`
+    pub flexible_length: Option<f32>,
+    /// Length in business days instead of an `end`; the end is found by
+    /// counting forward from `start`, e.g. `businessDays = 5`
+    pub business_days: Option<u32>,
+    /// Whether `end` is the last day off (`true`) or the first day back;
+    /// overrides the config's `endInclusive`
+    pub end_inclusive: Option<bool>,
+    /// How much the trip matters, weighting it in `--health-score`; 1 unless
+    /// set, e.g. `priority = 3` for a wedding
+    pub priority: Option<f32>,
+    /// Taken as unpaid leave: costs pay, valued with `hourlyRate`, instead
+    /// of PTO
+    #[serde(default)]
+    pub unpaid: bool,
+    /// Household member the trip belongs to, from `--household`.
+    #[serde(skip)]
+    pub owner: Option<String>,
+}
+
+/// One person's schedule in a household sharing a single PTO bank.
+#[derive(Debug, Clone)]
+pub struct Member {
+    pub name: String,
+    pub path: Box<Path>,
+}
+
+/// Parse a `--household` argument: `NAME=PATH`, or just `PATH` to name the
+/// member after the file.
+pub fn parse_member(arg: &str) -> Result<Member, String> {
+    let (name, path) = match arg.split_once('=') {
+        Some((name, path)) => (name.trim().to_string(), Path::new(path.trim())),
+        None => {
+            let path = Path::new(arg);
+            let stem = path
+                .file_stem()
+                .ok_or_else(|| format!("no schedule file in '{}'", arg))?;
+            (stem.to_string_lossy().into_owned(), path)
+        }
+    };
+    if name.is_empty() {
+        return Err(format!("expected NAME=PATH, got '{}'", arg));
+    }
+    Ok(Member {
+        name,
+        path: path.into(),
+    })
+}
+
+/// Where to find the schedule when `--sched` isn't given: `schedule.toml`
+/// in the working directory, then `$XDG_CONFIG_HOME/vacay/schedule.toml`
+/// (`~/.config` when unset). The paths tried are returned on failure.
+pub fn default_schedule() -> Result<PathBuf, Vec<PathBuf>> {
+    let config_dir = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+    let candidates: Vec<PathBuf> = [Some(PathBuf::from("schedule.toml"))]
+        .into_iter()
+        .chain([config_dir.map(|dir| dir.join("vacay").join("schedule.toml"))])
+        .flatten()
+        .collect();
+    candidates
+        .iter()
+        .find(|path| path.is_file())
+        .cloned()
+        .ok_or(candidates)
+}
+
+/// Stand-in for a date a schedule entry left out.
+fn unscheduled() -> NaiveDate {
+    NaiveDate::MIN
+}
+
+impl Vacation {
+    /// Whether `start` and `end` were both given.
+    pub fn is_dated(&self) -> bool {
+        self.start != unscheduled() && self.has_end()
+    }
+
+    pub fn has_end(&self) -> bool {
+        self.end != unscheduled()
+    }
+
+    /// Chronological order: by start, then end, then name.
+    pub fn sort_key(&self) -> (NaiveDate, NaiveDate, Option<&str>) {
+        (self.start, self.end, self.name.as_deref())
+    }
+
+    /// A recurring vacation as one single-day vacation per matching weekday;
+    /// any other vacation as itself.
+    pub fn expand(self) -> Vec<Vacation> {
+        let Some(weekday) = self.recur_weekly else {
+            return vec![self];
+        };
+        self.start
+            .iter_days()
+            .take_while(|date| *date <= self.end)
+            .filter(|date| date.weekday() == weekday)
+            .map(|date| Vacation {
+                start: date,
+                end: date,
+                recur_weekly: None,
+                ..self.clone()
+            })
+            .collect()
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FractionScope {
+    #[default]
+    All,
+    Start,
+    End,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(default)]
+pub struct Booked {
+    pub flights: bool,
+    pub hotel: bool,
+    pub approved: bool,
+}
+
+impl Booked {
+    const ITEMS: usize = 3;
+
+    pub fn done(&self) -> usize {
+        [self.flights, self.hotel, self.approved]
+            .iter()
+            .filter(|b| **b)
+            .count()
+    }
+
+    pub fn summary(&self) -> String {
+        format!("{}/{}", self.done(), Self::ITEMS)
+    }
+
+    /// The checklist items still outstanding.
+    pub fn missing(&self) -> Vec<&'static str> {
+        [
+            (self.flights, "flights"),
+            (self.hotel, "hotel"),
+            (self.approved, "approval"),
+        ]
+        .into_iter()
+        .filter(|(done, _)| !done)
+        .map(|(_, item)| item)
+        .collect()
+    }
+}
+
+/// Fold `overlay` into `base`: tables merge key by key, arrays are
+/// concatenated and any other value in `overlay` replaces the one in `base`.
+fn merge(base: &mut Table, overlay: Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base)), Value::Table(overlay)) => merge(base, overlay),
+            (Some(Value::Array(base)), Value::Array(overlay)) => base.extend(overlay),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Explain a TOML error in `contents`, read from `source`. Interactive runs
+/// get the offending line with a caret under the problem and a hint at the
+/// usual fix; anything else gets the plain error.
+pub fn parse_error(source: &str, contents: &str, e: &toml::de::Error) -> String {
+    let interactive = std::io::stdin().is_terminal() && std::io::stdout().is_terminal();
+    let Some(span) = e.span().filter(|_| interactive) else {
+        return format!("could not parse {}: {}", source, e);
+    };
+
+    let line_start = contents[..span.start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = contents[span.start..]
+        .find('\n')
+        .map_or(contents.len(), |i| span.start + i);
+    let line_number = contents[..span.start].matches('\n').count() + 1;
+    let column = contents[line_start..span.start].chars().count() + 1;
+    let width = contents[span.start..span.end.min(line_end)]
+        .chars()
+        .count()
+        .max(1);
+    let gutter = " ".repeat(line_number.to_string().len());
+
+    let mut out = format!(
+        "could not parse {} at line {}, column {}: {}\n{} |\n{} | {}\n{} | {}{}",
+        source,
+        line_number,
+        column,
+        e.message().trim(),
+        gutter,
+        line_number,
+        &contents[line_start..line_end],
+        gutter,
+        " ".repeat(column - 1),
+        "^".repeat(width)
+    );
+    if let Some(hint) = hint(e.message()) {
+        out += &format!("\nhint: {}", hint);
+    }
+    out
+}
+
+/// A likely fix for common mistakes, going by the error message.
+fn hint(message: &str) -> Option<&'static str> {
+    let message = message.to_lowercase();
+    let hint = if message.contains("date") {
+        "write dates as YYYY-MM-DD without quotes, e.g. start = 2026-07-04"
+    } else if message.contains("missing field") {
+        "add the missing setting, or check its spelling and capitalization"
+    } else if message.contains("unknown variant") {
+        "check the spelling of the value against the allowed ones listed"
+    } else if message.contains("duplicate key") {
+        "each key may appear only once per table; remove or rename one"
+    } else if message.contains("invalid type") {
+        "check the value's type: numbers unquoted, text in \"quotes\", lists in [brackets]"
+    } else if message.contains("expected") || message.contains("invalid") {
+        "check for a missing `=`, quote, comma or closing bracket"
+    } else {
+        return None;
+    };
+    Some(hint)
+}
+
+/// A loaded config and where its settings came from.
+pub struct Loaded {
+    pub config: Config,
+    /// The merged document as TOML.
+    pub merged: String,
+    pub sources: Sources,
+}
+
+/// The files that set each top-level config key, in merge order. Arrays list
+/// every contributor; anything else only the one that won.
+#[derive(Debug, Default)]
+pub struct Sources(BTreeMap<String, Vec<String>>);
+
+impl Sources {
+    fn record(&mut self, table: &Table, source: &str) {
+        for (key, value) in table {
+            let files = self.0.entry(key.clone()).or_default();
+            if !value.is_array() {
+                files.clear();
+            }
+            files.push(source.to_string());
+        }
+    }
+
+    /// Where `key` was set, or `None` if no file set it.
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.0.get(key).map(|files| files.join(", "))
+    }
+}
+
+/// Read and merge the config files in order, later files overriding earlier
+/// ones. A `base` document, such as a shared company policy, sits beneath
+/// all of them.
+pub fn load(paths: &[Box<Path>], base: Option<&str>) -> Result<Loaded, String> {
+    let mut sources = Sources::default();
+    let mut merged = match base {
+        Some(base) => toml::from_str(base).map_err(|e| format!("could not parse policy: {}", e))?,
+        None => Table::new(),
+    };
+    sources.record(&merged, "policy");
+    let mut files: Vec<(String, String)> = base
+        .map(|base| ("policy".to_string(), base.to_string()))
+        .into_iter()
+        .collect();
+    for path in paths {
+        if !path.exists() {
+            return Err(format!(
+                "config file {} with list of Garmin holidays is required",
+                path.display()
+            ));
+        }
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+        let table: Table = toml::from_str(&contents)
+            .map_err(|e| parse_error(&path.display().to_string(), &contents, &e))?;
+        sources.record(&table, &path.display().to_string());
+        merge(&mut merged, table);
+        files.push((path.display().to_string(), contents));
+    }
+    // Round-trip through text: untagged holiday entries can't be read
+    // straight from a `toml::Value`
+    let merged = toml::to_string(&merged).map_err(|e| format!("invalid config: {}", e))?;
+    // Positions in the merged text mean nothing to the user, so point at the
+    // last file that fails the same way on its own
+    let config = toml::from_str(&merged).map_err(|e| {
+        files
+            .iter()
+            .rev()
+            .find_map(|(source, contents)| {
+                let own = toml::from_str::<Config>(contents).err()?;
+                (own.message() == e.message()).then(|| parse_error(source, contents, &own))
+            })
+            .unwrap_or_else(|| parse_error("the merged config", &merged, &e))
+    })?;
+    Ok(Loaded {
+        config,
+        merged,
+        sources,
+    })
+}
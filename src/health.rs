@@ -0,0 +1,35 @@
+//! A single 0–100 number for how well the plan holds up.
+//!
+//! Each trip has a weight, its `priority` (1 unless set). A trip that isn't
+//! affordable, for whatever reason, costs `100 × weight ÷ total weight`
+//! points, so the score is the weighted share of trips that go ahead: an
+//! unaffordable priority-3 trip costs three times what a priority-1 trip
+//! does.
+
+use crate::sim::Outcome;
+
+/// A trip's weight in the score.
+pub fn weight(outcome: &Outcome) -> f32 {
+    outcome.vacation.priority.unwrap_or(1.0).max(0.0)
+}
+
+/// The points each trip costs the score, in schedule order; zero for
+/// affordable trips.
+pub fn penalties(outcomes: &[Outcome]) -> Vec<f32> {
+    let total: f32 = outcomes.iter().map(weight).sum();
+    outcomes
+        .iter()
+        .map(|o| {
+            if o.affordable() || total == 0.0 {
+                0.0
+            } else {
+                100.0 * weight(o) / total
+            }
+        })
+        .collect()
+}
+
+/// 100 when every trip is affordable, 0 when none is.
+pub fn score(outcomes: &[Outcome]) -> f32 {
+    100.0 - penalties(outcomes).iter().sum::<f32>()
+}
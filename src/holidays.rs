@@ -0,0 +1,352 @@
+use crate::csv;
+use chrono::{Datelike, NaiveDate, Weekday};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Where a holiday came from, ordered from least to most specific. When two
+/// sources list the same date, the more specific one wins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HolidaySource {
+    /// Bundled official holidays, only ever compared against.
+    Region,
+    Csv,
+    Config,
+    Cli,
+}
+
+impl HolidaySource {
+    pub fn label(self) -> &'static str {
+        match self {
+            HolidaySource::Region => "region",
+            HolidaySource::Csv => "csv",
+            HolidaySource::Config => "config",
+            HolidaySource::Cli => "cli",
+        }
+    }
+}
+
+/// A holiday as written in the config: either a bare date or a table with a
+/// name, e.g. `{ date = 2026-01-01, name = "New Year's Day" }`. A `fraction`
+/// makes it a partial holiday, e.g. `fraction = 0.5` for a half day off.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum HolidayEntry {
+    Date(#[serde(with = "toml_datetime_compat")] NaiveDate),
+    Named {
+        #[serde(with = "toml_datetime_compat")]
+        date: NaiveDate,
+        name: Option<String>,
+        fraction: Option<f32>,
+    },
+}
+
+impl HolidayEntry {
+    pub fn into_holiday(self, source: HolidaySource) -> Holiday {
+        match self {
+            HolidayEntry::Date(date) => Holiday {
+                date,
+                name: None,
+                source,
+                fraction: None,
+            },
+            HolidayEntry::Named {
+                date,
+                name,
+                fraction,
+            } => Holiday {
+                date,
+                name,
+                source,
+                fraction,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Holiday {
+    pub date: NaiveDate,
+    pub name: Option<String>,
+    pub source: HolidaySource,
+    /// Share of the day off, for a partial holiday; a whole day when unset.
+    pub fraction: Option<f32>,
+}
+
+impl Holiday {
+    /// Off for only part of the day.
+    pub fn is_partial(&self) -> bool {
+        self.fraction.is_some_and(|f| f < 1.0)
+    }
+}
+
+/// Parse a `--holiday` argument of the form `YYYY-MM-DD` or `YYYY-MM-DD=Name`.
+pub fn parse_holiday_arg(arg: &str) -> Result<Holiday, String> {
+    let (date, name) = match arg.split_once('=') {
+        Some((date, name)) => (date, Some(name.trim().to_string())),
+        None => (arg, None),
+    };
+    let date = NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+        .map_err(|e| format!("invalid holiday date '{}': {}", date, e))?;
+    Ok(Holiday {
+        date,
+        name: name.filter(|n| !n.is_empty()),
+        source: HolidaySource::Cli,
+        fraction: None,
+    })
+}
+
+/// Where to find the holidays in a CSV file. A column is a 1-based number or
+/// a header name; without a header row, named columns fall back to the
+/// first column for the date and the second for the name.
+pub struct CsvLayout<'a> {
+    pub date_col: &'a str,
+    pub name_col: &'a str,
+    pub date_format: &'a str,
+}
+
+/// Read holidays from a published CSV such as a government holiday list.
+pub fn load_csv(path: &Path, layout: &CsvLayout) -> Result<Vec<Holiday>, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+    let mut records = csv::parse(&text).into_iter().peekable();
+
+    let index = |spec: &str| spec.parse::<usize>().ok().map(|n| n.saturating_sub(1));
+    let (mut date_col, mut name_col) = (index(layout.date_col), index(layout.name_col));
+    let parses = |field: Option<&String>| {
+        field.is_some_and(|f| NaiveDate::parse_from_str(f.trim(), layout.date_format).is_ok())
+    };
+    if let Some(header) = records.next_if(|r| !parses(r.get(date_col.unwrap_or(0)))) {
+        let find = |spec: &str| {
+            csv::column(&header, spec)
+                .ok_or_else(|| format!("{} has no column '{}'", path.display(), spec))
+        };
+        if date_col.is_none() {
+            date_col = Some(find(layout.date_col)?);
+        }
+        if name_col.is_none() {
+            name_col = csv::column(&header, layout.name_col);
+        }
+    }
+    let date_col = date_col.unwrap_or(0);
+    let name_col = name_col.unwrap_or(1);
+
+    records
+        .enumerate()
+        .map(|(i, record)| {
+            let field = |col: usize| record.get(col).map(|f| f.trim()).unwrap_or_default();
+            let date = NaiveDate::parse_from_str(field(date_col), layout.date_format)
+                .map_err(|e| format!("{} row {}: bad date: {}", path.display(), i + 1, e))?;
+            let name = Some(field(name_col).to_string()).filter(|n| !n.is_empty());
+            Ok(Holiday {
+                date,
+                name,
+                source: HolidaySource::Csv,
+                fraction: None,
+            })
+        })
+        .collect()
+}
+
+/// Merge holidays from every source into one chronologically sorted list with
+/// a single entry per date. The most specific source wins a conflict; if it
+/// didn't name the holiday, the name is taken from the next most specific
+/// source that did.
+pub fn normalize(holidays: impl IntoIterator<Item = Holiday>) -> Vec<Holiday> {
+    let mut by_date: BTreeMap<NaiveDate, Vec<Holiday>> = BTreeMap::new();
+    for holiday in holidays {
+        by_date.entry(holiday.date).or_default().push(holiday);
+    }
+
+    by_date
+        .into_values()
+        .map(|mut candidates| {
+            // Stable sort keeps the original order among equally specific entries
+            candidates.sort_by_key(|h| std::cmp::Reverse(h.source));
+            let name = candidates.iter().find_map(|h| h.name.clone());
+            let mut winner = candidates.swap_remove(0);
+            winner.name = name;
+            winner
+        })
+        .collect()
+}
+
+/// Regions with bundled holiday rules.
+pub const REGIONS: &[&str] = &["us"];
+
+/// The official holidays of `region` in `year`, on the days they're observed.
+pub fn official(region: &str, year: i32) -> Result<Vec<Holiday>, String> {
+    let rules: &[(&str, Rule)] = match region.to_ascii_lowercase().as_str() {
+        "us" => &US_FEDERAL,
+        _ => {
+            return Err(format!(
+                "no bundled holidays for region '{}'; known regions: {}",
+                region,
+                REGIONS.join(", ")
+            ));
+        }
+    };
+    let mut holidays: Vec<Holiday> = rules
+        .iter()
+        .filter_map(|(name, rule)| {
+            Some(Holiday {
+                date: rule.date(year)?,
+                name: Some(name.to_string()),
+                source: HolidaySource::Region,
+                fraction: None,
+            })
+        })
+        .collect();
+    holidays.sort_by_key(|h| h.date);
+    Ok(holidays)
+}
+
+/// How a holiday's date is found each year.
+#[derive(Debug, Clone, Copy)]
+enum Rule {
+    /// A fixed date, moved to Friday or Monday when it falls on a weekend.
+    Fixed { month: u32, day: u32 },
+    /// The nth weekday of the month; 0 is the last one.
+    Nth { month: u32, weekday: Weekday, n: u8 },
+}
+
+impl Rule {
+    fn date(self, year: i32) -> Option<NaiveDate> {
+        match self {
+            Rule::Fixed { month, day } => {
+                let date = NaiveDate::from_ymd_opt(year, month, day)?;
+                Some(match date.weekday() {
+                    Weekday::Sat => date.pred_opt()?,
+                    Weekday::Sun => date.succ_opt()?,
+                    _ => date,
+                })
+            }
+            Rule::Nth {
+                month,
+                weekday,
+                n: 0,
+            } => {
+                let next_month = NaiveDate::from_ymd_opt(year, month, 1)?
+                    .checked_add_months(chrono::Months::new(1))?;
+                let last = next_month.pred_opt()?;
+                let back = (7 + last.weekday().num_days_from_monday()
+                    - weekday.num_days_from_monday())
+                    % 7;
+                last.checked_sub_days(chrono::Days::new(back.into()))
+            }
+            Rule::Nth { month, weekday, n } => {
+                NaiveDate::from_weekday_of_month_opt(year, month, weekday, n)
+            }
+        }
+    }
+}
+
+const US_FEDERAL: [(&str, Rule); 11] = [
+    ("New Year's Day", Rule::Fixed { month: 1, day: 1 }),
+    (
+        "Martin Luther King Jr. Day",
+        Rule::Nth {
+            month: 1,
+            weekday: Weekday::Mon,
+            n: 3,
+        },
+    ),
+    (
+        "Washington's Birthday",
+        Rule::Nth {
+            month: 2,
+            weekday: Weekday::Mon,
+            n: 3,
+        },
+    ),
+    (
+        "Memorial Day",
+        Rule::Nth {
+            month: 5,
+            weekday: Weekday::Mon,
+            n: 0,
+        },
+    ),
+    ("Juneteenth", Rule::Fixed { month: 6, day: 19 }),
+    ("Independence Day", Rule::Fixed { month: 7, day: 4 }),
+    (
+        "Labor Day",
+        Rule::Nth {
+            month: 9,
+            weekday: Weekday::Mon,
+            n: 1,
+        },
+    ),
+    (
+        "Columbus Day",
+        Rule::Nth {
+            month: 10,
+            weekday: Weekday::Mon,
+            n: 2,
+        },
+    ),
+    ("Veterans Day", Rule::Fixed { month: 11, day: 11 }),
+    (
+        "Thanksgiving Day",
+        Rule::Nth {
+            month: 11,
+            weekday: Weekday::Thu,
+            n: 4,
+        },
+    ),
+    ("Christmas Day", Rule::Fixed { month: 12, day: 25 }),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn holiday(day: u32, name: Option<&str>, source: HolidaySource) -> Holiday {
+        Holiday {
+            date: NaiveDate::from_ymd_opt(2026, 12, day).unwrap(),
+            name: name.map(str::to_string),
+            source,
+            fraction: None,
+        }
+    }
+
+    #[test]
+    fn more_specific_source_wins() {
+        let merged = normalize([
+            holiday(24, Some("Imported"), HolidaySource::Csv),
+            holiday(24, Some("Configured"), HolidaySource::Config),
+            holiday(24, Some("Given"), HolidaySource::Cli),
+            holiday(25, Some("Imported"), HolidaySource::Csv),
+            holiday(25, Some("Configured"), HolidaySource::Config),
+        ]);
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].source, HolidaySource::Cli);
+        assert_eq!(merged[0].name.as_deref(), Some("Given"));
+        assert_eq!(merged[1].source, HolidaySource::Config);
+        assert_eq!(merged[1].name.as_deref(), Some("Configured"));
+    }
+
+    #[test]
+    fn name_falls_back_to_less_specific_source() {
+        let merged = normalize([
+            holiday(24, Some("Christmas Eve"), HolidaySource::Csv),
+            holiday(24, None, HolidaySource::Config),
+            holiday(24, None, HolidaySource::Cli),
+        ]);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].source, HolidaySource::Cli);
+        assert_eq!(merged[0].name.as_deref(), Some("Christmas Eve"));
+    }
+
+    #[test]
+    fn sorted_by_date() {
+        let merged = normalize([
+            holiday(31, None, HolidaySource::Config),
+            holiday(1, None, HolidaySource::Cli),
+            holiday(15, None, HolidaySource::Csv),
+        ]);
+        let days: Vec<u32> = merged.iter().map(|h| h.date.day()).collect();
+        assert_eq!(days, [1, 15, 31]);
+    }
+}
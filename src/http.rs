@@ -0,0 +1,58 @@
+//! Minimal HTTP for the optional network features (`webhook`,
+//! `policy-url`). Requests are made with the system `curl` so the default
+//! build stays free of an HTTP stack.
+
+#[cfg(feature = "webhook")]
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// POST a JSON body, ignoring the response.
+#[cfg(feature = "webhook")]
+pub fn post(url: &str, body: &str) -> Result<(), String> {
+    let mut child = Command::new("curl")
+        .args([
+            "-sS",
+            "-f",
+            "-X",
+            "POST",
+            "-H",
+            "Content-Type: application/json",
+        ])
+        .args(["--data-binary", "@-", url])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("could not run curl: {}", e))?;
+    child
+        .stdin
+        .take()
+        .expect("curl stdin should be piped")
+        .write_all(body.as_bytes())
+        .map_err(|e| format!("could not send webhook body: {}", e))?;
+    let status = child
+        .wait()
+        .map_err(|e| format!("curl did not finish: {}", e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("curl exited with {}", status))
+    }
+}
+
+/// GET a document as text.
+#[cfg(feature = "policy-url")]
+pub fn get(url: &str) -> Result<String, String> {
+    let output = Command::new("curl")
+        .args(["-sS", "-f", "-L", "--max-time", "10", url])
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("could not run curl: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "curl exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    String::from_utf8(output.stdout).map_err(|e| format!("response is not UTF-8: {}", e))
+}
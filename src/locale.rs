@@ -0,0 +1,144 @@
+//! Decimal and thousands separators for numbers shown to people, and how
+//! negative balances are shown. Machine output (JSON, metrics) always uses
+//! plain `.` decimals and skips this.
+
+use std::fmt;
+use std::sync::OnceLock;
+
+/// How numbers are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Locale {
+    decimal: char,
+    thousands: Option<char>,
+}
+
+impl Locale {
+    /// `1234.5`: the output vacay always had.
+    pub const PLAIN: Locale = Locale {
+        decimal: '.',
+        thousands: None,
+    };
+
+    /// Look up a locale tag such as `de_DE.UTF-8`, `fr` or `en-US` by its
+    /// language. `auto` reads `LC_ALL`, `LC_NUMERIC` and `LANG`.
+    pub fn parse(tag: &str) -> Result<Locale, String> {
+        if tag == "auto" {
+            let env = ["LC_ALL", "LC_NUMERIC", "LANG"]
+                .into_iter()
+                .find_map(|var| std::env::var(var).ok().filter(|v| !v.is_empty()));
+            return Ok(env.map_or(Locale::PLAIN, |tag| {
+                Locale::parse(&tag).unwrap_or(Locale::PLAIN)
+            }));
+        }
+        let language = tag
+            .split(['_', '-', '.'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let (decimal, thousands) = match language.as_str() {
+            "c" | "posix" => ('.', None),
+            "en" | "ja" | "ko" | "zh" | "he" | "th" => ('.', Some(',')),
+            "de" | "es" | "it" | "nl" | "pt" | "id" | "tr" | "da" | "el" => (',', Some('.')),
+            "fr" | "ru" | "pl" | "cs" | "sk" | "sv" | "nb" | "no" | "fi" | "uk" | "hu" => {
+                (',', Some(' '))
+            }
+            _ => return Err(format!("unknown locale '{}'", tag)),
+        };
+        Ok(Locale { decimal, thousands })
+    }
+
+    /// Rewrite a number formatted with `.` decimals in this locale.
+    fn apply(self, formatted: &str) -> String {
+        let (sign, unsigned) = match formatted.strip_prefix(['-', '+']) {
+            Some(rest) => (&formatted[..1], rest),
+            None => ("", formatted),
+        };
+        let (integer, fraction) = match unsigned.split_once('.') {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (unsigned, None),
+        };
+        let mut out = sign.to_string();
+        for (i, digit) in integer.chars().enumerate() {
+            if i > 0 && (integer.len() - i) % 3 == 0 {
+                out.extend(self.thousands);
+            }
+            out.push(digit);
+        }
+        if let Some(fraction) = fraction {
+            out.push(self.decimal);
+            out += fraction;
+        }
+        out
+    }
+}
+
+static LOCALE: OnceLock<Locale> = OnceLock::new();
+
+/// Choose the locale for the rest of the run; only the first call counts.
+pub fn set(locale: Locale) {
+    let _ = LOCALE.set(locale);
+}
+
+fn current() -> Locale {
+    LOCALE.get().copied().unwrap_or(Locale::PLAIN)
+}
+
+/// A number displayed in the current locale. Honors the precision and `+`
+/// flags, so `format!("{:+.2}", Num(x))` works like it does for `x`.
+#[derive(Debug, Clone, Copy)]
+pub struct Num(pub f32);
+
+impl fmt::Display for Num {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let formatted = match (f.precision(), f.sign_plus()) {
+            (Some(places), true) => format!("{:+.*}", places, self.0),
+            (Some(places), false) => format!("{:.*}", places, self.0),
+            (None, true) => format!("{:+}", self.0),
+            (None, false) => self.0.to_string(),
+        };
+        f.write_str(&current().apply(&formatted))
+    }
+}
+
+static CLAMP_NEGATIVE: OnceLock<bool> = OnceLock::new();
+
+/// Show negative balances as zero with the deficit owed alongside, as some
+/// payroll systems present advanced PTO.
+pub fn clamp_negative() {
+    let _ = CLAMP_NEGATIVE.set(true);
+}
+
+/// A balance displayed like [`Num`], floored at zero under
+/// `--clamp-negative-display`; [`Balance::owed`] then carries the deficit.
+#[derive(Debug, Clone, Copy)]
+pub struct Balance(pub f32);
+
+impl Balance {
+    fn clamped(self) -> bool {
+        self.0 < 0.0 && CLAMP_NEGATIVE.get().copied().unwrap_or(false)
+    }
+
+    /// The number displayed: zero in place of a clamped deficit.
+    pub fn shown(self) -> f32 {
+        if self.clamped() { 0.0 } else { self.0 }
+    }
+
+    /// ` (owed: X.XX)` when the display is clamped, else nothing.
+    pub fn owed(self) -> String {
+        if self.clamped() {
+            format!(" (owed: {:.2})", Num(-self.0))
+        } else {
+            String::new()
+        }
+    }
+}
+
+impl fmt::Display for Balance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let shown = Num(self.shown());
+        match f.precision() {
+            Some(places) => write!(f, "{:.*}", places, shown),
+            None => write!(f, "{}", shown),
+        }
+    }
+}
@@ -1,223 +1,1949 @@
-use chrono::{self, Datelike, NaiveDate, Weekday};
-use clap::Parser;
+mod analysis;
+mod badge;
+mod calendar;
+mod config;
+mod csv;
+mod health;
+mod holidays;
+#[cfg(any(feature = "webhook", feature = "policy-url"))]
+mod http;
+mod json;
+mod locale;
+mod metrics;
+#[cfg(feature = "policy-url")]
+mod policy;
+mod reconcile;
+mod sim;
+mod statement;
+mod status;
+mod table;
+mod template;
+mod timing;
+mod warnings;
+
+use chrono::{self, Datelike, Months, NaiveDate, Weekday};
+use clap::{Parser, Subcommand, ValueEnum};
 use color_print::{ceprintln, cprintln};
-use serde::Deserialize;
+use config::{GrantDay, Member, Schedule, Vacation};
+use holidays::{Holiday, HolidaySource};
+use locale::{Balance, Locale, Num};
+use sim::{AccrualFrequency, BalanceAnchor, EventKind, Policy, RateChange};
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
 use std::path::Path;
 use std::process::exit;
+use table::{Column, GroupBy};
 use tabled::{Table, Tabled};
-use toml::value::{Date, Datetime};
-use toml_datetime_compat::FromToTomlDateTime;
+
+const WEEKS_PER_YEAR: f32 = 52.0;
+/// Trips starting within this many days should be fully booked.
+const IMMINENT_DAYS: i64 = 30;
+/// How many of the longest gaps `--detect-gaps` lists.
+const GAPS_SHOWN: usize = 3;
+/// Float error `--validate-balance` lets through, in hours.
+const BALANCE_TOLERANCE: f32 = 0.01;
 
 #[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
+#[command(version, about, long_about = None, subcommand_negates_reqs = true)]
 struct Args {
-    #[arg(long = "accrual", short = 'a')]
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[arg(long = "accrual", short = 'a', conflicts_with = "annual_hours")]
     pto_hrs_per_wk: Option<f32>,
+    /// Annual PTO total in hours; the weekly accrual is derived as hours / 52
+    #[arg(long = "annual-hours")]
+    annual_hours: Option<f32>,
     #[arg(long = "bank", short = 'b')]
     pto_bank: Option<f32>,
-    #[arg(long = "config", short = 'c', default_value = "config.toml")]
-    path_to_config: Box<Path>,
+    /// Config file; repeat to layer files, later ones overriding earlier ones
+    #[arg(
+        long = "config",
+        short = 'c',
+        default_value = "config.toml",
+        global = true
+    )]
+    path_to_config: Vec<Box<Path>>,
+    /// Schedule file; defaults to schedule.toml here, then in
+    /// $XDG_CONFIG_HOME/vacay
     #[arg(long = "sched", short = 's')]
-    path_to_sched: Box<Path>,
+    path_to_sched: Option<Box<Path>>,
+    /// Household member's schedule as NAME=PATH, or PATH to use the file
+    /// name (repeatable). All trips share one bank and accrual rate.
+    #[arg(long = "household", value_parser = config::parse_member)]
+    household: Vec<Member>,
+    /// Extra holiday as YYYY-MM-DD or YYYY-MM-DD=Name (repeatable)
+    #[arg(long = "holiday", value_parser = holidays::parse_holiday_arg, global = true)]
+    extra_holidays: Vec<Holiday>,
+    /// Import holidays from a CSV file, such as an official government list
+    #[arg(long = "holidays-csv", value_name = "PATH", global = true)]
+    holidays_csv: Option<Box<Path>>,
+    /// Date column of --holidays-csv: a header name or 1-based number
+    #[arg(long = "date-col", default_value = "date", global = true)]
+    date_col: String,
+    /// Name column of --holidays-csv: a header name or 1-based number
+    #[arg(long = "name-col", default_value = "name", global = true)]
+    name_col: String,
+    /// chrono format of the dates in --holidays-csv
+    #[arg(long = "date-format", default_value = "%Y-%m-%d", global = true)]
+    date_format: String,
     #[arg(long = "verbose")]
     verbose: bool,
+    /// Decimal and thousands separators for numbers, e.g. `de_DE` or `auto`
+    /// to follow LC_ALL/LC_NUMERIC/LANG; the default is plain `1234.5`
+    #[arg(long = "locale", value_parser = Locale::parse, global = true)]
+    locale: Option<Locale>,
+    /// Print the projected balance on this date and every event leading up to it
+    #[arg(long = "explain-date", value_name = "DATE")]
+    explain_date: Option<NaiveDate>,
+    /// Explain why one trip, given by name or table position, is or isn't affordable
+    #[arg(long = "explain-affordability", value_name = "TRIP")]
+    explain_affordability: Option<String>,
+    /// Show which days of one trip, given by name or table position, cost
+    /// PTO and why the others are free
+    #[arg(long = "explain-holidays", value_name = "TRIP")]
+    explain_holidays: Option<String>,
+    /// Reschedule one trip, given by name or table position, to start on
+    /// this date and show what changes
+    #[arg(long = "move", value_name = "TRIP=DATE", value_parser = analysis::parse_move)]
+    move_trip: Option<analysis::Move>,
+    /// Compare the holiday list against a region's official holidays, e.g.
+    /// `us`, for the years it covers, and print the differences
+    #[arg(long = "audit-holidays-against", value_name = "REGION")]
+    audit_holidays_against: Option<String>,
+    /// Print nothing when every trip is affordable; otherwise print the
+    /// usual output. Warnings still go to stderr
+    #[arg(long = "quiet-if-ok")]
+    quiet_if_ok: bool,
+    /// Write warnings to this file instead of stderr
+    #[arg(long = "warnings-file", value_name = "PATH", global = true)]
+    warnings_file: Option<Box<Path>>,
+    /// Report how long each phase of the run took
+    #[arg(long = "benchmark")]
+    benchmark: bool,
+    /// Leave the job on this date and report the PTO paid out
+    #[arg(long = "payout-on", value_name = "DATE")]
+    payout_on: Option<NaiveDate>,
+    /// Check that the simulated balance equals the sum of its accruals,
+    /// deductions and other movements
+    #[arg(long = "validate-balance")]
+    validate_balance: bool,
+    /// Show negative balances as 0.00 with the hours owed alongside
+    #[arg(long = "clamp-negative-display", global = true)]
+    clamp_negative_display: bool,
+    /// Add a column of the accrual credited during each trip
+    #[arg(long = "show-accrual-offset")]
+    show_accrual_offset: bool,
+    /// Write a per-year statement of the PTO balance to this CSV file
+    #[arg(long = "fiscal-summary-csv", value_name = "PATH")]
+    fiscal_summary_csv: Option<Box<Path>>,
+    /// Show table dates like "Mon, Jul 1 (in 3 weeks)"
+    #[arg(long = "pretty-dates")]
+    pretty_dates: bool,
+    /// Score the plan from 0 to 100 by the priority-weighted share of trips
+    /// that are affordable
+    #[arg(long = "health-score")]
+    health_score: bool,
+    /// Write an SVG status badge of the plan to `--output` or stdout
+    #[arg(long = "badge")]
+    badge: bool,
+    /// Print the trips as they go into the simulation, after expansion and
+    /// filtering, without simulating them
+    #[arg(long = "list-trips")]
+    list_trips: bool,
+    /// Describe the schedule itself: trip count, lengths and busiest month
+    #[arg(long = "schedule-stats")]
+    schedule_stats: bool,
+    /// Print only the next upcoming trip and whether it's affordable
+    #[arg(long = "next")]
+    next: bool,
+    /// Write a JSON summary of the result to this file
+    #[arg(long = "status-file", value_name = "PATH")]
+    status_file: Option<Box<Path>>,
+    /// Print a commented schedule skeleton with this many placeholder trips
+    #[arg(long = "plan-template", value_name = "TRIPS")]
+    plan_template: Option<usize>,
+    /// Print every resolved setting and where it came from, then exit
+    #[arg(long = "print-config")]
+    print_config: bool,
+    /// Write generated files here instead of stdout
+    #[arg(long = "output", short = 'o', value_name = "PATH")]
+    output: Option<Box<Path>>,
+    /// Report the longest stretches without a vacation
+    #[arg(long = "detect-gaps")]
+    detect_gaps: bool,
+    /// Change the accrual rate from a date on, as YYYY-MM-DD=RATE (repeatable)
+    #[arg(long = "simulate-raise", value_name = "DATE=RATE", value_parser = sim::parse_rate_change)]
+    raises: Vec<RateChange>,
+    /// Reset the projected balance to a known value on a date, as YYYY-MM-DD=HOURS (repeatable)
+    #[arg(long = "assume-balance-at", value_name = "DATE=HOURS", value_parser = sim::parse_balance_anchor)]
+    anchors: Vec<BalanceAnchor>,
+    /// Compare the projection with actual balances from a date,balance CSV
+    #[arg(long = "verify-against", value_name = "CSV")]
+    verify_against: Option<Box<Path>>,
+    /// Largest projected vs actual difference, in hours, that isn't flagged
+    #[arg(long = "tolerance", default_value_t = 1.0)]
+    tolerance: f32,
+    /// Output format; `prometheus` prints only metrics for a textfile
+    /// collector, `json` is for `--list-trips`
+    #[arg(long = "format", value_enum, default_value_t = Format::Table)]
+    format: Format,
+    /// Comma-separated table columns to show, in order
+    #[arg(long = "columns", value_enum, value_delimiter = ',')]
+    columns: Option<Vec<Column>>,
+    /// Split the table into sections with subtotals
+    #[arg(long = "group-by", value_enum)]
+    group_by: Option<GroupBy>,
+    /// Summarize the accrual between consecutive trips in the table
+    #[arg(long = "show-interim", conflicts_with = "group_by")]
+    show_interim: bool,
+    /// Move trip starts that fall on a weekend or holiday forward, and ends
+    /// back, to the nearest business day inside the trip
+    #[arg(long = "snap-dates")]
+    snap_dates: bool,
+    /// Prefix trip names with their icon; ignored with --ascii or --no-color
+    #[arg(long = "icons")]
+    icons: bool,
+    /// Most PTO hours all planned trips together may use
+    #[arg(long = "budget", value_name = "HOURS")]
+    budget: Option<f32>,
+    /// Find the lowest weekly accrual rate that affords every trip
+    #[arg(long = "break-even")]
+    break_even: bool,
+    /// Also show each month with a vacation as a calendar grid
+    #[arg(long = "calendar")]
+    calendar: bool,
+    /// Draw calendar markers without color, and show no icons
+    #[arg(long = "no-color")]
+    no_color: bool,
+    /// Draw calendar markers with ASCII characters only, and show no icons
+    #[arg(long = "ascii")]
+    ascii: bool,
+    /// POST a JSON summary to this URL when any vacation is unaffordable or
+    /// PTO is about to be forfeited
+    #[cfg(feature = "webhook")]
+    #[arg(long = "notify-webhook", value_name = "URL")]
+    notify_webhook: Option<String>,
+    /// How many days ahead a forfeiture triggers `--notify-webhook`
+    #[cfg(feature = "webhook")]
+    #[arg(
+        long = "forfeiture-alert-days",
+        value_name = "DAYS",
+        default_value_t = 30
+    )]
+    forfeiture_alert_days: u32,
+    /// Fetch a shared TOML policy from this URL and use it beneath the local config
+    #[cfg(feature = "policy-url")]
+    #[arg(long = "policy-url", value_name = "URL", global = true)]
+    policy_url: Option<String>,
+    /// Hours a fetched policy stays cached before it is fetched again
+    #[cfg(feature = "policy-url")]
+    #[arg(
+        long = "policy-ttl",
+        value_name = "HOURS",
+        default_value_t = 24,
+        global = true
+    )]
+    policy_ttl: u64,
 }
 
-#[derive(Deserialize, Debug)]
-struct Config {
-    ptoHoursPerWeek: Option<f32>,
-    ptoBank: Option<f32>,
-    holidays: Vec<Date>,
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// List the merged, deduplicated holiday set
+    Holidays {
+        #[arg(long, value_enum, default_value_t = SortOrder::Asc)]
+        sort: SortOrder,
+    },
 }
 
-#[derive(Deserialize, Debug)]
-struct Schedule {
-    vacations: Vec<Vacation>,
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Format {
+    Table,
+    Prometheus,
+    /// Only for `--list-trips`
+    Json,
 }
 
-#[derive(Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord)]
-struct Vacation {
-    #[serde(with = "toml_datetime_compat")]
-    start: NaiveDate,
-    #[serde(with = "toml_datetime_compat")]
-    end: NaiveDate,
-    name: Option<String>,
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum SortOrder {
+    Asc,
+    Desc,
 }
 
 #[derive(Tabled)]
-struct VacationRow {
+struct TripRow {
     #[tabled(rename = "Vacation")]
     name: String,
     #[tabled(rename = "Start")]
     start: NaiveDate,
     #[tabled(rename = "End")]
     end: NaiveDate,
-    #[tabled(rename = "Days")]
-    days: usize,
-    #[tabled(rename = "Hours")]
-    hours: i32,
-    #[tabled(rename = "Status")]
-    status: String,
+    #[tabled(rename = "Category")]
+    category: String,
+    #[tabled(rename = "Who")]
+    owner: String,
+    #[tabled(rename = "Notes")]
+    notes: String,
 }
 
-fn main() {
-    let mut args = Args::parse();
+/// Print the trips about to be simulated for `--list-trips`, as a table or a
+/// JSON array.
+fn list_trips(vacations: &[Vacation], format: Format) {
+    if format == Format::Json {
+        let trips: Vec<String> = vacations
+            .iter()
+            .map(|v| {
+                json::Object::new()
+                    .opt_str("name", v.name.as_deref())
+                    .str("start", &v.start.to_string())
+                    .str("end", &v.end.to_string())
+                    .opt_str("category", v.category.as_deref())
+                    .opt_str("owner", v.owner.as_deref())
+                    .raw(
+                        "fraction",
+                        v.fraction.map_or("null".to_string(), |f| f.to_string()),
+                    )
+                    .bool("unpaid", v.unpaid)
+                    .bool("flexible", v.flexible_length.is_some())
+                    .build()
+            })
+            .collect();
+        println!("[{}]", trips.join(","));
+        return;
+    }
+    let rows = vacations.iter().map(|v| {
+        let notes: Vec<String> = [
+            v.flexible_length.map(|_| "suggested start".to_string()),
+            v.fraction.map(|f| format!("fraction {}", Num(f))),
+            v.unpaid.then(|| "unpaid".to_string()),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        TripRow {
+            name: v.name.clone().unwrap_or("Unnamed".to_string()),
+            start: v.start,
+            end: v.end,
+            category: v.category.clone().unwrap_or_default(),
+            owner: v.owner.clone().unwrap_or_default(),
+            notes: notes.join(", "),
+        }
+    });
+    println!("{}", Table::new(rows));
+}
 
-    if !args.path_to_config.exists() {
-        println!("Error: config file with list of Garmin holidays is required");
-        exit(1);
+#[derive(Tabled)]
+struct DayRow {
+    #[tabled(rename = "Date")]
+    date: NaiveDate,
+    #[tabled(rename = "Day")]
+    weekday: Weekday,
+    #[tabled(rename = "PTO")]
+    charge: String,
+    #[tabled(rename = "Why")]
+    reason: String,
+}
+
+/// Classify every day of `vacation` for `--explain-holidays`: what it cost
+/// and, for free days, what made them free.
+fn explain_holidays(vacation: &Vacation, policy: &Policy, names: &BTreeMap<NaiveDate, String>) {
+    let rules = vacation
+        .category
+        .as_ref()
+        .and_then(|category| policy.categories.get(category));
+    let holidays = rules
+        .and_then(|r| r.holidays.as_ref())
+        .unwrap_or(&policy.holidays);
+    let charges: BTreeMap<NaiveDate, f32> =
+        sim::pto_charges(vacation, policy).into_iter().collect();
+    let named = |date: NaiveDate| match names.get(&date) {
+        Some(name) => format!("holiday: {}", name),
+        None => "holiday".to_string(),
+    };
+
+    let rows: Vec<DayRow> = vacation
+        .start
+        .iter_days()
+        .take_while(|date| *date <= vacation.end)
+        .map(|date| {
+            let weekday = date.weekday();
+            let reason = match charges.get(&date) {
+                Some(_) if rules.is_none_or(|r| r.holidays.is_none()) => {
+                    match policy.partial_holidays.get(&date) {
+                        Some(off) => match names.get(&date) {
+                            Some(name) => format!("PTO less {} off for {}", Num(*off), name),
+                            None => format!("PTO less {} off for a partial holiday", Num(*off)),
+                        },
+                        None => "PTO".to_string(),
+                    }
+                }
+                Some(_) => "PTO".to_string(),
+                None => match rules.and_then(|r| r.workdays.as_ref()) {
+                    Some(workdays) if !workdays.contains(&weekday) => format!(
+                        "not a {} workday",
+                        vacation.category.as_deref().unwrap_or_default()
+                    ),
+                    Some(_) => named(date),
+                    None if matches!(weekday, Weekday::Sat | Weekday::Sun) => "weekend".to_string(),
+                    None if policy.days_off.contains(&weekday) => "recurring day off".to_string(),
+                    None if holidays.contains(&date) => named(date),
+                    None => "free".to_string(),
+                },
+            };
+            DayRow {
+                date,
+                weekday,
+                charge: Num(charges.get(&date).copied().unwrap_or(0.0)).to_string(),
+                reason,
+            }
+        })
+        .collect();
+
+    let name = vacation.name.as_deref().unwrap_or("Unnamed");
+    cprintln!(
+        "<blue>{} ({} to {})</blue>",
+        name,
+        vacation.start,
+        vacation.end
+    );
+    println!("{}", Table::new(rows));
+    println!(
+        "{} days, {} of them costing {} PTO days",
+        (vacation.end - vacation.start).num_days() + 1,
+        charges.values().filter(|c| **c > 0.0).count(),
+        Num(charges.values().sum())
+    );
+}
+
+#[derive(Tabled)]
+struct HolidayRow {
+    #[tabled(rename = "Date")]
+    date: NaiveDate,
+    #[tabled(rename = "Day")]
+    weekday: Weekday,
+    #[tabled(rename = "Holiday")]
+    name: String,
+    #[tabled(rename = "Source")]
+    source: &'static str,
+}
+
+/// Walk through everything that decides whether `outcome` is affordable,
+/// for `--explain-affordability`.
+fn explain_affordability(simulation: &sim::Simulation, policy: &Policy, outcome: &sim::Outcome) {
+    let start = outcome.vacation.start;
+    cprintln!(
+        "<blue>{} ({} to {})</blue>",
+        outcome.name,
+        start,
+        outcome.vacation.end
+    );
+    println!(
+        "Starting balance on {}: {:.2} hours",
+        simulation.today,
+        Num(simulation.opening_balance)
+    );
+    for event in simulation.events.iter().take_while(|e| e.date < start) {
+        println!("{}", event.describe());
     }
-    let config_contents = fs::read_to_string(args.path_to_config)
-        .expect("The config file should exist and be readable.");
-    let config: Config =
-        toml::from_str(&config_contents).expect("The config file should be parseable.");
+    println!("Balance at trip start: {:.2} hours", Num(outcome.available));
+    println!(
+        "Needed: {:.2} hours ({} days)",
+        Num(outcome.hours),
+        Num(outcome.days)
+    );
 
-    if args.pto_hrs_per_wk.is_none() {
-        args.pto_hrs_per_wk = Some(config.ptoHoursPerWeek.expect("Error: Missing accrual rate"));
+    match outcome.status {
+        sim::Status::Affordable => cprintln!(
+            "<green>Affordable, with {:.2} hours to spare</green>",
+            Num(outcome.available - outcome.hours)
+        ),
+        sim::Status::Unaffordable => {
+            let short = outcome.hours - outcome.available;
+            cprintln!("<red>Unaffordable: {:.2} hours short</red>", Num(short));
+            // Waiting can't help if nothing accrues or the cap is below the cost
+            let accrues = policy.credits_to_earn(start, f32::MIN_POSITIVE).is_some()
+                && policy.accrual_cap.is_none_or(|cap| cap >= outcome.hours);
+            match policy.credits_to_earn(start, short).filter(|_| accrues) {
+                Some((count, by)) => println!(
+                    "{} more {} would cover it, the last on {}",
+                    count,
+                    policy.credit_label(),
+                    by
+                ),
+                // The search only looks two years ahead
+                None if accrues => println!(
+                    "This trip is not affordable within two years of accrual after it starts."
+                ),
+                None => print_never_affordable("This trip"),
+            }
+        }
+        sim::Status::AfterEmployment => cprintln!(
+            "<red>Starts after employment ends on {}</red>",
+            policy.employment_end.unwrap()
+        ),
+        sim::Status::TooLong => cprintln!(
+            "<red>Over the {} day limit per trip</red>",
+            Num(policy.max_pto_days_per_trip.unwrap())
+        ),
+        sim::Status::OverBudget => cprintln!(
+            "<red>Over the {} hour PTO budget</red>",
+            Num(policy.budget.unwrap())
+        ),
+        sim::Status::Unpaid => match outcome.lost_pay {
+            Some(pay) => cprintln!(
+                "<yellow>Unpaid leave: no PTO used, {:.2} hours of pay worth {:.2}</yellow>",
+                Num(outcome.hours),
+                Num(pay)
+            ),
+            None => cprintln!(
+                "<yellow>Unpaid leave: no PTO used, {:.2} hours of pay</yellow>",
+                Num(outcome.hours)
+            ),
+        },
     }
-    if args.pto_bank.is_none() {
-        args.pto_bank = Some(config.ptoBank.expect("Error: Missing banked PTO value"));
+}
+
+/// What to try when no amount of waiting makes `what` affordable.
+fn print_never_affordable(what: &str) {
+    println!(
+        "{} is not affordable under the current accrual rate and bank.",
+        what
+    );
+    println!("Try raising the starting bank or accrual rate, or shortening trips.");
+}
+
+fn entries(count: usize) -> String {
+    match count {
+        1 => "1 entry".to_string(),
+        n => format!("{} entries", n),
     }
+}
 
-    cprintln!("Let's go on <green><i>vacation</i></green>!");
-    ceprintln!("PTO bank:    <blue>{}</blue> hours", args.pto_bank.unwrap());
+/// The greeting and the bank, rate and holiday count the run starts from.
+fn print_header(
+    args: &Args,
+    annual_hours: Option<f32>,
+    hourly_accrual: Option<f32>,
+    holiday_count: usize,
+) {
+    if args.format == Format::Table
+        && !args.next
+        && !args.badge
+        && !args.schedule_stats
+        && !args.list_trips
+        && args.explain_holidays.is_none()
+    {
+        cprintln!("Let's go on <green><i>vacation</i></green>!");
+    }
     ceprintln!(
-        "PTO accrual: <blue>{}</blue> hours / week",
-        args.pto_hrs_per_wk.unwrap()
+        "PTO bank:    <blue>{}</blue> hours",
+        Num(args.pto_bank.unwrap())
     );
+    match (annual_hours, hourly_accrual) {
+        (Some(hours), _) => ceprintln!(
+            "PTO accrual: <blue>{:.2}</blue> hours / week (from {} hours / year)",
+            Num(args.pto_hrs_per_wk.unwrap()),
+            Num(hours)
+        ),
+        (None, Some(_)) => ceprintln!(
+            "PTO accrual: <blue>{:.2}</blue> hours / week (from hours worked)",
+            Num(args.pto_hrs_per_wk.unwrap())
+        ),
+        (None, None) => ceprintln!(
+            "PTO accrual: <blue>{}</blue> hours / week",
+            Num(args.pto_hrs_per_wk.unwrap())
+        ),
+    }
     ceprintln!(
         "Garmin holidays in config: <blue>{}</blue> days",
-        config.holidays.len()
+        holiday_count
     );
+}
 
-    let sched_contents =
-        fs::read_to_string(args.path_to_sched).expect("The schedule file should be readable.");
-    let sched: Schedule =
-        toml::from_str(&sched_contents).expect("The schedule file should be parseable.");
+/// Close the last phase, report the timings for `--benchmark` and exit;
+/// every mode that prints something and stops goes through here.
+fn finish(mut stopwatch: timing::Stopwatch, benchmark: bool) -> ! {
+    stopwatch.lap("render");
+    if benchmark {
+        stopwatch.report();
+    }
+    exit(0);
+}
 
-    let mut vacations = sched.vacations;
-    vacations.sort_unstable();
+/// An empty plan is ok, so `--quiet-if-ok` says nothing about it either.
+fn no_vacations(args: &Args) -> ! {
+    if !args.quiet_if_ok {
+        println!("No vacations in your schedule :(");
+    }
+    exit(0);
+}
+
+/// Weekdays as a range when they run on without a gap, e.g. "Mon-Fri",
+/// else one by one.
+fn weekday_list(days: &[Weekday]) -> String {
+    match days {
+        [] => "none".to_string(),
+        [first, .., last]
+            if days.len() > 2 && days.windows(2).all(|pair| pair[0].succ() == pair[1]) =>
+        {
+            format!("{}-{}", first, last)
+        }
+        _ => days
+            .iter()
+            .map(|day| day.to_string())
+            .collect::<Vec<_>>()
+            .join(", "),
+    }
+}
+
+/// Print each resolved setting with its value and source for `--print-config`.
+fn print_config(
+    args: &Args,
+    merged_config: &str,
+    sources: &config::Sources,
+    holidays: &[Holiday],
+    annual_hours: Option<f32>,
+    hourly_accrual: Option<f32>,
+) {
+    let document: toml::Table = toml::from_str(merged_config).unwrap_or_default();
+    let from_config = |key: &str| sources.get(key).unwrap_or_default();
+    let mut builder = tabled::builder::Builder::default();
+    builder.push_record(["Setting", "Value", "Source"]);
+
+    let accrual = match (args.pto_hrs_per_wk, annual_hours) {
+        (Some(rate), _) => [
+            format!("{} hours / week", Num(rate)),
+            "--accrual".to_string(),
+        ],
+        (None, Some(hours)) => [
+            format!(
+                "{:.2} hours / week (from {} hours / year)",
+                Num(hours / WEEKS_PER_YEAR),
+                Num(hours)
+            ),
+            match args.annual_hours {
+                Some(_) => "--annual-hours".to_string(),
+                None => from_config("annualHours"),
+            },
+        ],
+        (None, None) if hourly_accrual.is_some() => [
+            format!(
+                "{:.2} hours / week (from hours worked)",
+                Num(hourly_accrual.unwrap())
+            ),
+            from_config("payPeriodHours"),
+        ],
+        (None, None) => match document.get("ptoHoursPerWeek") {
+            Some(rate) => [
+                format!("{} hours / week", rate),
+                from_config("ptoHoursPerWeek"),
+            ],
+            None => ["(unset)".to_string(), String::new()],
+        },
+    };
+    let bank = match (args.pto_bank, document.get("ptoBank")) {
+        (Some(bank), _) => [format!("{} hours", Num(bank)), "--bank".to_string()],
+        (None, Some(bank)) => [format!("{} hours", bank), from_config("ptoBank")],
+        (None, None) => ["(unset)".to_string(), String::new()],
+    };
+    let has_source = |source| holidays.iter().any(|h| h.source == source);
+    let holiday_sources: Vec<String> = args
+        .holidays_csv
+        .as_ref()
+        .filter(|_| has_source(HolidaySource::Csv))
+        .map(|path| path.display().to_string())
+        .into_iter()
+        .chain(sources.get("holidays"))
+        .chain(has_source(HolidaySource::Cli).then(|| "--holiday".to_string()))
+        .collect();
+    let [accrual, accrual_source] = accrual;
+    let [bank, bank_source] = bank;
+    builder.push_record(["accrual".to_string(), accrual, accrual_source]);
+    builder.push_record(["bank".to_string(), bank, bank_source]);
+    builder.push_record([
+        "hoursPerDay".to_string(),
+        sim::HOURS_PER_DAY.to_string(),
+        "built-in".to_string(),
+    ]);
+    let weekdays = |value: Option<&toml::Value>| -> Vec<Weekday> {
+        value
+            .and_then(|v| v.clone().try_into().ok())
+            .unwrap_or_default()
+    };
+    let days_off = weekdays(document.get("recurringDaysOff"));
+    let workdays: Vec<Weekday> = [
+        Weekday::Mon,
+        Weekday::Tue,
+        Weekday::Wed,
+        Weekday::Thu,
+        Weekday::Fri,
+    ]
+    .into_iter()
+    .filter(|day| !days_off.contains(day))
+    .collect();
+    builder.push_record([
+        "workdays".to_string(),
+        weekday_list(&workdays),
+        if days_off.is_empty() {
+            "built-in".to_string()
+        } else {
+            from_config("recurringDaysOff")
+        },
+    ]);
+    // Categories count their own workdays in place of the ones above
+    if let Some(categories) = document.get("categories").and_then(|c| c.as_table()) {
+        for (name, category) in categories {
+            if let Some(days) = category.get("workdays") {
+                builder.push_record([
+                    format!("workdays ({})", name),
+                    weekday_list(&weekdays(Some(days))),
+                    from_config("categories"),
+                ]);
+            }
+        }
+    }
+    builder.push_record([
+        "timezone".to_string(),
+        chrono::Local::now().offset().to_string(),
+        "system".to_string(),
+    ]);
+    builder.push_record([
+        "holidays".to_string(),
+        format!("{} days", holidays.len()),
+        holiday_sources.join(", "),
+    ]);
+    if !args.raises.is_empty() {
+        builder.push_record([
+            "rateChanges".to_string(),
+            entries(args.raises.len()),
+            "--simulate-raise".to_string(),
+        ]);
+    }
+    for (key, value) in &document {
+        if ["ptoHoursPerWeek", "annualHours", "ptoBank", "holidays"].contains(&key.as_str()) {
+            continue;
+        }
+        let value = match value {
+            toml::Value::Array(list) => entries(list.len()),
+            value => value.to_string(),
+        };
+        builder.push_record([key.clone(), value, from_config(key)]);
+    }
+    println!("{}", builder.build());
+}
 
+fn main() {
+    let mut args = Args::parse();
+    let mut stopwatch = timing::Stopwatch::start();
+    locale::set(args.locale.unwrap_or(Locale::PLAIN));
+    if args.clamp_negative_display {
+        locale::clamp_negative();
+    }
+    if let Some(path) = &args.warnings_file
+        && let Err(e) = warnings::to_file(path)
+    {
+        println!("Error: could not write {}: {}", path.display(), e);
+        exit(1);
+    }
+    if args.format == Format::Json && !args.list_trips {
+        println!("Error: --format json is only supported with --list-trips");
+        exit(1);
+    }
     let today = chrono::Local::now().date_naive();
-    vacations.retain(|vac| vac.end > today);
 
-    if vacations.len() == 0 {
-        println!("No vacations in your schedule :(");
+    if let Some(trips) = args.plan_template {
+        let template = template::plan(today, trips);
+        match &args.output {
+            Some(path) => {
+                if let Err(e) = fs::write(path, template) {
+                    println!("Error: could not write {}: {}", path.display(), e);
+                    exit(1);
+                }
+            }
+            None => print!("{}", template),
+        }
         exit(0);
     }
 
-    // Convert holidays to NaiveDate for easier comparison
-    let holidays: Vec<NaiveDate> = config
-        .holidays
+    #[cfg(feature = "policy-url")]
+    let policy = args.policy_url.as_deref().and_then(|url| {
+        let ttl = std::time::Duration::from_secs(args.policy_ttl * 60 * 60);
+        policy::fetch(url, ttl)
+            .inspect_err(|e| {
+                warnings::warn(format_args!(
+                    "could not fetch policy, using local config only: {}",
+                    e
+                ))
+            })
+            .ok()
+    });
+    #[cfg(not(feature = "policy-url"))]
+    let policy: Option<String> = None;
+
+    let config::Loaded {
+        mut config,
+        merged: merged_config,
+        sources,
+    } = match config::load(&args.path_to_config, policy.as_deref()) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            println!("Error: {}", e);
+            exit(1);
+        }
+    };
+    if args.verbose && (args.path_to_config.len() > 1 || policy.is_some()) {
+        cprintln!("<dim>Effective config:\n{}</dim>", merged_config);
+    }
+
+    let imported = match &args.holidays_csv {
+        Some(path) => {
+            let layout = holidays::CsvLayout {
+                date_col: &args.date_col,
+                name_col: &args.name_col,
+                date_format: &args.date_format,
+            };
+            match holidays::load_csv(path, &layout) {
+                Ok(imported) => imported,
+                Err(e) => {
+                    println!("Error: {}", e);
+                    exit(1);
+                }
+            }
+        }
+        None => Vec::new(),
+    };
+
+    // Merge every holiday source into one sorted, deduplicated set
+    let holidays = holidays::normalize(
+        imported.into_iter().chain(
+            std::mem::take(&mut config.holidays)
+                .into_iter()
+                .map(|h| h.into_holiday(HolidaySource::Config))
+                .chain(args.extra_holidays.drain(..)),
+        ),
+    );
+
+    if let Some(Command::Holidays { sort }) = args.command {
+        let mut rows: Vec<HolidayRow> = holidays
+            .into_iter()
+            .map(|h| HolidayRow {
+                date: h.date,
+                weekday: h.date.weekday(),
+                name: h.name.unwrap_or_default(),
+                source: h.source.label(),
+            })
+            .collect();
+        if let SortOrder::Desc = sort {
+            rows.reverse();
+        }
+        println!("{}", Table::new(rows));
+        exit(0);
+    }
+
+    if let Some(region) = &args.audit_holidays_against {
+        let years: BTreeSet<i32> = holidays.iter().map(|h| h.date.year()).collect();
+        let mut official = Vec::new();
+        for year in &years {
+            match holidays::official(region, *year) {
+                Ok(list) => official.extend(list),
+                Err(e) => {
+                    println!("Error: {}", e);
+                    exit(1);
+                }
+            }
+        }
+        let listed: BTreeSet<NaiveDate> = holidays.iter().map(|h| h.date).collect();
+        let standard: BTreeSet<NaiveDate> = official.iter().map(|h| h.date).collect();
+        let missing: Vec<&Holiday> = official
+            .iter()
+            .filter(|h| !listed.contains(&h.date))
+            .collect();
+        let extra: Vec<&Holiday> = holidays
+            .iter()
+            .filter(|h| !standard.contains(&h.date))
+            .collect();
+        let covered: Vec<String> = years.iter().map(|y| y.to_string()).collect();
+        println!(
+            "Holidays vs. {} official holidays for {}:",
+            region,
+            if covered.is_empty() {
+                "no years".to_string()
+            } else {
+                covered.join(", ")
+            }
+        );
+        if missing.is_empty() && extra.is_empty() {
+            println!("No differences");
+        }
+        // `+` would be added to match the region, `-` isn't in it
+        let mut diff: Vec<(char, &Holiday)> = missing
+            .into_iter()
+            .map(|h| ('+', h))
+            .chain(extra.into_iter().map(|h| ('-', h)))
+            .collect();
+        diff.sort_by_key(|(_, h)| h.date);
+        for (sign, holiday) in diff {
+            let line = format!(
+                "{} {} {}",
+                sign,
+                holiday.date,
+                holiday.name.as_deref().unwrap_or_default()
+            );
+            match sign {
+                '+' => cprintln!("<green>{}</green>", line.trim_end()),
+                _ => cprintln!("<red>{}</red>", line.trim_end()),
+            }
+        }
+        finish(stopwatch, args.benchmark);
+    }
+
+    // Only one setting may define the accrual rate; the CLI overrides the config
+    let hourly_accrual = match config.hourly_accrual() {
+        Ok(rate) => rate,
+        Err(e) => {
+            println!("Error: {}", e);
+            exit(1);
+        }
+    };
+    let rate_settings = [
+        config.pto_hours_per_week.is_some(),
+        config.annual_hours.is_some(),
+        hourly_accrual.is_some(),
+    ];
+    if rate_settings.iter().filter(|set| **set).count() > 1 {
+        println!(
+            "Error: config sets more than one of ptoHoursPerWeek, annualHours and payPeriodHours; keep only one"
+        );
+        exit(1);
+    }
+    let annual_hours = match (args.pto_hrs_per_wk, args.annual_hours) {
+        (Some(_), _) => None,
+        (None, Some(hours)) => Some(hours),
+        (None, None) => config.annual_hours,
+    };
+    let hourly_accrual =
+        hourly_accrual.filter(|_| args.pto_hrs_per_wk.is_none() && annual_hours.is_none());
+    if args.print_config {
+        print_config(
+            &args,
+            &merged_config,
+            &sources,
+            &holidays,
+            annual_hours,
+            hourly_accrual,
+        );
+        finish(stopwatch, args.benchmark);
+    }
+    if args.pto_hrs_per_wk.is_none() {
+        args.pto_hrs_per_wk = Some(match (annual_hours, hourly_accrual) {
+            (Some(hours), _) => hours / WEEKS_PER_YEAR,
+            (None, Some(rate)) => rate,
+            // A yearly grant can stand in for weekly accrual
+            (None, None) => config
+                .pto_hours_per_week
+                .or(config.annual_grant.map(|_| 0.0))
+                .expect("Error: Missing accrual rate"),
+        });
+    }
+    if args.pto_bank.is_none() {
+        args.pto_bank = Some(config.pto_bank.expect("Error: Missing banked PTO value"));
+    }
+
+    let holiday_count = holidays.len();
+    if !args.quiet_if_ok {
+        print_header(&args, annual_hours, hourly_accrual, holiday_count);
+    }
+
+    if args.path_to_sched.is_none() && args.household.is_empty() {
+        match config::default_schedule() {
+            Ok(path) => args.path_to_sched = Some(path.into()),
+            Err(tried) => {
+                let tried: Vec<String> = tried.iter().map(|p| p.display().to_string()).collect();
+                println!(
+                    "Error: no schedule given with --sched and none found at {}",
+                    tried.join(" or ")
+                );
+                exit(1);
+            }
+        }
+    }
+    if args.verbose
+        && let Some(path) = &args.path_to_sched
+    {
+        cprintln!("<dim>Schedule: {}</dim>", path.display());
+    }
+
+    // Every household member's trips join one timeline drawing on one bank
+    let schedules = args.path_to_sched.iter().map(|path| (None, path)).chain(
+        args.household
+            .iter()
+            .map(|member| (Some(&member.name), &member.path)),
+    );
+    let mut entries = Vec::new();
+    for (owner, sched_path) in schedules {
+        let sched_contents = match fs::read_to_string(sched_path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                println!("Error: could not read {}: {}", sched_path.display(), e);
+                exit(1);
+            }
+        };
+        let sched: Schedule = match toml::from_str(&sched_contents) {
+            Ok(sched) => sched,
+            Err(e) => {
+                let source = sched_path.display().to_string();
+                println!(
+                    "Error: {}",
+                    config::parse_error(&source, &sched_contents, &e)
+                );
+                exit(1);
+            }
+        };
+        entries.extend(sched.vacations.into_iter().map(|vacation| Vacation {
+            owner: owner.cloned(),
+            ..vacation
+        }));
+    }
+
+    stopwatch.lap("parse");
+
+    if let Some(holiday) = holidays
         .iter()
-        .map(|d| NaiveDate::from_ymd_opt(d.year.into(), d.month.into(), d.day.into()).unwrap())
+        .find(|h| h.fraction.is_some_and(|f| f <= 0.0 || f > 1.0))
+    {
+        println!(
+            "Error: holiday on {} has a fraction outside 0 to 1",
+            holiday.date
+        );
+        exit(1);
+    }
+    let holiday_names: BTreeMap<NaiveDate, String> = holidays
+        .iter()
+        .filter_map(|h| Some((h.date, h.name.clone()?)))
         .collect();
+    let (partial_holidays, holidays): (Vec<Holiday>, Vec<Holiday>) =
+        holidays.into_iter().partition(|h| h.is_partial());
+    let partial_holidays: BTreeMap<NaiveDate, f32> = partial_holidays
+        .iter()
+        .filter_map(|h| Some((h.date, h.fraction?)))
+        .collect();
+    let holidays: Vec<NaiveDate> = holidays.iter().map(|h| h.date).collect();
 
-    // Start with current PTO balance as f32 for fractional hours
-    let mut pto_balance = args.pto_bank.unwrap();
-    let pto_accrual_per_week = args.pto_hrs_per_wk.unwrap();
+    let snapshots = match args.verify_against.as_deref().map(reconcile::load) {
+        Some(Ok(snapshots)) => snapshots,
+        Some(Err(e)) => {
+            println!("Error: {}", e);
+            exit(1);
+        }
+        None => Vec::new(),
+    };
 
-    // Get current date and advance to next Sunday (not including today)
-    let mut current_date = today;
-    // First advance past today
-    current_date = current_date.succ_opt().unwrap();
-    // Then find the next Sunday
-    while current_date.weekday() != Weekday::Sun {
-        current_date = current_date.succ_opt().unwrap();
+    if config.accrual_rounding.is_some_and(|step| step <= 0.0) {
+        println!("Error: accrualRounding must be a positive number of hours");
+        exit(1);
     }
 
-    // Process each vacation in chronological order
-    let mut rows = Vec::new();
+    let probation = match (config.hire_date, config.probation_days) {
+        (Some(hire_date), Some(days)) => Some(sim::Probation {
+            hire_date,
+            end: hire_date
+                .checked_add_days(chrono::Days::new(days.into()))
+                .unwrap_or_else(|| {
+                    println!(
+                        "Error: {}",
+                        sim::too_far_out(format!("{} days after {}", days, hire_date))
+                    );
+                    exit(1);
+                }),
+            retroactive: config.retroactive_after_probation,
+        }),
+        (None, Some(_)) => {
+            println!("Error: probationDays requires hireDate");
+            exit(1);
+        }
+        (_, None) if config.retroactive_after_probation => {
+            println!("Error: retroactiveAfterProbation requires probationDays");
+            exit(1);
+        }
+        (_, None) => None,
+    };
+
+    if !matches!(
+        config.accrual_frequency,
+        AccrualFrequency::Weekly | AccrualFrequency::PerBusinessDay
+    ) {
+        println!("Error: accrualFrequency must be \"weekly\" or \"perBusinessDay\"");
+        exit(1);
+    }
 
-    for vacation in &vacations {
-        // Calculate PTO days needed (inclusive of start and end date)
-        // Skip weekends and holidays
-        let mut pto_days_needed = 0;
-        let mut date = vacation.start;
+    let max_horizon = match today.checked_add_months(Months::new(config.max_horizon_years * 12)) {
+        Some(date) if config.max_horizon_years > 0 => date,
+        _ => {
+            println!("Error: maxHorizonYears must be a positive number of years");
+            exit(1);
+        }
+    };
+    let mut truncated = false;
 
-        while date <= vacation.end {
-            // Check if it's a weekday (Monday = 1, Sunday = 7)
-            let weekday = date.weekday();
-            if weekday != Weekday::Sat && weekday != Weekday::Sun {
-                // Check if it's not a holiday
-                if !holidays.contains(&date) {
-                    pto_days_needed += 1;
-                }
+    let annual_grant = match (config.annual_grant, config.annual_grant_on) {
+        (None, _) => None,
+        (Some(hours), GrantDay::Jan1) => Some(sim::AnnualGrant {
+            hours,
+            on: NaiveDate::from_ymd_opt(today.year(), 1, 1).unwrap(),
+        }),
+        (Some(hours), GrantDay::Anniversary) => match config.hire_date {
+            Some(on) => Some(sim::AnnualGrant { hours, on }),
+            None => {
+                println!("Error: annualGrantOn = \"anniversary\" requires hireDate");
+                exit(1);
             }
-            date = date.succ_opt().unwrap();
-        }
+        },
+    };
 
-        // Calculate PTO hours needed (assuming 8 hours per day)
-        let pto_hours_needed = (pto_days_needed * 8) as f32;
+    let policy = Policy {
+        bank: args.pto_bank.unwrap(),
+        accrual_per_week: args.pto_hrs_per_wk.unwrap(),
+        rate_changes: args.raises.clone(),
+        accrual_sources: config.accrual_sources,
+        accrual_frequency: config.accrual_frequency,
+        holidays,
+        partial_holidays,
+        partial_holiday_rule: config.partial_holiday_rule,
+        days_off: config.recurring_days_off,
+        categories: config
+            .categories
+            .into_iter()
+            .map(|(name, category)| (name, category.into_rules()))
+            .collect(),
+        other_banks: config.banks,
+        transfers: config.transfers,
+        allow_negative_transfers: config.allow_negative_transfers,
+        carryover_cap: config.carryover_cap,
+        accrual_cap: config.accrual_cap,
+        cap_overflow: config.cap_overflow_mode,
+        accrual_rounding: config
+            .accrual_rounding
+            .map(|step| (step, config.accrual_rounding_mode)),
+        probation,
+        work_schedule: config.work_schedule,
+        no_accrual: config.no_accrual_ranges,
+        clawbacks: config.deductions,
+        anchors: args.anchors.clone(),
+        resets: config.reset_events,
+        accrual_before_deduction: config.accrual_before_deduction,
+        starting_today: config.starting_today,
+        annual_grant,
+        accrue_today: config.accrue_today,
+        // Leaving on the payout date ends employment there
+        employment_end: config
+            .employment_end
+            .into_iter()
+            .chain(args.payout_on)
+            .min(),
+        max_pto_days_per_trip: config.max_pto_days_per_trip,
+        budget: args.budget,
+        cash_out: config.cash_out,
+        project_until: snapshots
+            .iter()
+            .map(|s| s.date)
+            .chain(args.explain_date)
+            .chain(args.payout_on)
+            .max(),
+        hourly_rate: config.hourly_rate,
+        max_horizon: Some(max_horizon),
+    };
 
-        // Simulate time passing from current_date to vacation start
-        // Add PTO accrual for each Sunday that passes
-        while current_date < vacation.start {
-            // Add PTO accrual on Sunday
-            pto_balance += pto_accrual_per_week;
+    let mut vacations = Vec::new();
+    let mut flexible = Vec::new();
+    for mut vacation in entries {
+        if vacation.flexible_length.is_some() {
+            flexible.push(vacation);
+            continue;
+        }
+        let name = vacation.name.clone().unwrap_or("Unnamed".to_string());
+        if let Some(days) = vacation.business_days {
+            if vacation.has_end() {
+                println!("Error: {} gives both an end and businessDays", name);
+                exit(1);
+            }
+            let Some(end) = sim::business_days_end(&vacation, days, &policy) else {
+                println!("Error: {} can't end after {} business days", name, days);
+                exit(1);
+            };
+            vacation.end = end;
             if args.verbose {
                 cprintln!(
-                    "<dim>Accrued PTO on {:?}: +{} hours (balance: {:.2})</dim>",
-                    current_date,
-                    pto_accrual_per_week,
-                    pto_balance
+                    "<dim>{}: {} business days from {} ends {}</dim>",
+                    name,
+                    days,
+                    vacation.start,
+                    end
                 );
             }
-            // Advance to next Sunday
-            for _ in 0..7 {
-                current_date = current_date.succ_opt().unwrap();
+        }
+        if !vacation.is_dated() {
+            println!("Error: {} needs a start and end, or a flexibleLength", name);
+            exit(1);
+        }
+        // Trip setting over config over the inclusive default; a derived end
+        // is always the last day off
+        let convention = vacation
+            .end_inclusive
+            .or(config.end_inclusive)
+            .filter(|_| vacation.business_days.is_none());
+        if convention == Some(false) {
+            match vacation.end.pred_opt().filter(|end| *end >= vacation.start) {
+                Some(end) => vacation.end = end,
+                None => {
+                    println!("Error: {} ends before it starts", name);
+                    exit(1);
+                }
             }
         }
+        if let Some(inclusive) = convention
+            && args.verbose
+        {
+            cprintln!(
+                "<dim>{}: end date {}, last day off {}</dim>",
+                name,
+                if inclusive { "inclusive" } else { "exclusive" },
+                vacation.end
+            );
+        }
+        let recurring = vacation.recur_weekly.is_some();
+        if recurring && vacation.end > max_horizon {
+            warnings::warn(format_args!(
+                "{} recurs only until {} (max horizon)",
+                name, max_horizon
+            ));
+            vacation.end = max_horizon;
+            truncated = true;
+        }
+        let expanded = vacation.expand();
+        if recurring && args.verbose {
+            cprintln!("<dim>Expanded {} into {} days</dim>", name, expanded.len());
+        }
+        vacations.extend(expanded);
+    }
+    vacations.sort_by(|a, b| a.sort_key().cmp(&b.sort_key()));
 
-        // Format vacation name
-        let name = vacation.name.as_deref().unwrap_or("Unnamed").to_string();
+    vacations.retain(|vac| vac.end > today);
 
-        // Check if we have enough PTO for this vacation
-        let status = if pto_balance >= pto_hours_needed {
-            // Deduct PTO for the vacation
-            pto_balance -= pto_hours_needed;
-            "✅".to_string()
-        } else {
-            "🚫".to_string()
-        };
+    if vacations.is_empty() && flexible.is_empty() {
+        no_vacations(&args);
+    }
 
-        // Add row to table
-        rows.push(VacationRow {
-            name,
-            start: vacation.start,
-            end: vacation.end,
-            days: pto_days_needed,
-            hours: pto_hours_needed as i32,
-            status,
-        });
+    if args.snap_dates {
+        for vacation in &mut vacations {
+            // Both ends snap inward so the trip never costs more than it did;
+            // one with no business day in it is left alone
+            let start = policy.next_business_day(vacation.start, true);
+            let end = policy.next_business_day(vacation.end, false);
+            if start > end {
+                continue;
+            }
+            for (label, from, to) in [("start", vacation.start, start), ("end", vacation.end, end)]
+            {
+                if from != to {
+                    warnings::warn(format_args!(
+                        "moved {} {} from {} ({}) to {} ({})",
+                        vacation.name.as_deref().unwrap_or("Unnamed"),
+                        label,
+                        from,
+                        from.weekday(),
+                        to,
+                        to.weekday()
+                    ));
+                }
+            }
+            vacation.start = start;
+            vacation.end = end;
+        }
+        vacations.sort_by(|a, b| a.sort_key().cmp(&b.sort_key()));
+    }
 
-        // Handle PTO accrual during the vacation (on Sundays)
-        let mut vacation_date = vacation.start;
-        while vacation_date <= vacation.end {
-            if vacation_date.weekday() == Weekday::Sun {
-                pto_balance += pto_accrual_per_week;
+    // Flexible trips are placed in schedule order, each around the ones
+    // placed before it
+    for vacation in flexible {
+        let name = vacation.name.as_deref().unwrap_or("Unnamed");
+        let length = vacation.flexible_length.unwrap();
+        match sim::earliest_start(today, &vacations, &vacation, length, &policy) {
+            Some(placed) => {
                 if args.verbose {
                     cprintln!(
-                        "<dim>Accrued PTO during vacation on {:?}: +{} hours (balance: {:.2})</dim>",
-                        vacation_date,
-                        pto_accrual_per_week,
-                        pto_balance
+                        "<dim>Earliest affordable start for {}: {}</dim>",
+                        name,
+                        placed.start
                     );
                 }
+                vacations.push(placed);
+                vacations.sort_by(|a, b| a.sort_key().cmp(&b.sort_key()));
             }
-            vacation_date = vacation_date.succ_opt().unwrap();
+            None => warnings::warn(format_args!(
+                "no start in the next two years affords {} ({} PTO days)",
+                name,
+                Num(length)
+            )),
         }
-        // Update current_date to day after vacation
-        current_date = vacation.end.succ_opt().unwrap();
-        // Advance to next Sunday
-        while current_date.weekday() != Weekday::Sun {
-            current_date = current_date.succ_opt().unwrap();
+    }
+    vacations.retain(|vacation| {
+        let within = vacation.end <= max_horizon;
+        if !within {
+            warnings::warn(format_args!(
+                "dropped {} ({} to {}): past the max horizon",
+                vacation.name.as_deref().unwrap_or("Unnamed"),
+                vacation.start,
+                vacation.end
+            ));
+            truncated = true;
         }
+        within
+    });
+    if vacations.is_empty() {
+        no_vacations(&args);
+    }
+    stopwatch.lap("expand");
+
+    if args.list_trips {
+        list_trips(&vacations, args.format);
+        finish(stopwatch, args.benchmark);
+    }
+
+    if let Some(trip) = &args.explain_holidays {
+        let Some(index) = analysis::find_trip(&vacations, trip) else {
+            println!("Error: no vacation named or numbered '{}'", trip);
+            exit(1);
+        };
+        explain_holidays(&vacations[index], &policy, &holiday_names);
+        finish(stopwatch, args.benchmark);
+    }
+
+    if args.verbose {
+        for range in &policy.no_accrual {
+            cprintln!(
+                "<dim>Accrual suspended from {:?} to {:?}</dim>",
+                range.start,
+                range.end
+            );
+        }
+    }
+    if args.explain_date.is_some_and(|date| date < today) {
+        println!("Error: --explain-date must not be in the past");
+        exit(1);
+    }
+    if args.schedule_stats {
+        let Some(stats) = analysis::stats(&vacations, &policy) else {
+            no_vacations(&args);
+        };
+        let name = |v: &Vacation| v.name.clone().unwrap_or("Unnamed".to_string());
+        println!("Trips: {}", stats.trips);
+        println!(
+            "Planned PTO: {:.2} days / {:.2} hours",
+            Num(stats.total_days),
+            Num(stats.total_hours)
+        );
+        println!("Average trip: {:.2} PTO days", Num(stats.average_days));
+        println!(
+            "Longest trip: {} ({} PTO days)",
+            name(stats.longest.0),
+            Num(stats.longest.1)
+        );
+        println!(
+            "Shortest trip: {} ({} PTO days)",
+            name(stats.shortest.0),
+            Num(stats.shortest.1)
+        );
+        let (month, count) = stats.busiest_month;
+        println!(
+            "Most common month: {} ({} {})",
+            month.name(),
+            count,
+            if count == 1 { "trip" } else { "trips" }
+        );
+        finish(stopwatch, args.benchmark);
+    }
+
+    if args.next {
+        // The first trip only depends on what comes before it
+        let simulation = match sim::simulate(today, &vacations[..1], &policy) {
+            Ok(simulation) => simulation,
+            Err(e) => {
+                println!("Error: {}", e);
+                exit(1);
+            }
+        };
+        let outcome = &simulation.outcomes[0];
+        let days_until = (outcome.vacation.start - today).num_days();
+        println!(
+            "{} {}: {} to {} ({}), {} PTO days / {} hours",
+            outcome.status.glyph(),
+            outcome.name,
+            outcome.vacation.start,
+            outcome.vacation.end,
+            match days_until {
+                ..=0 => "under way".to_string(),
+                1 => "tomorrow".to_string(),
+                days => format!("in {} days", days),
+            },
+            Num(outcome.days),
+            Num(outcome.hours)
+        );
+        finish(stopwatch, args.benchmark);
+    }
+
+    let simulation = match sim::simulate(today, &vacations, &policy) {
+        Ok(simulation) => simulation,
+        Err(e) => {
+            println!("Error: {}", e);
+            exit(1);
+        }
+    };
+    stopwatch.lap("simulate");
+    if truncated || simulation.truncated_at.is_some() {
+        warnings::warn(format_args!(
+            "projection truncated at {} (max horizon); raise maxHorizonYears to see further",
+            max_horizon
+        ));
+    }
+
+    if args.validate_balance {
+        let ledger = simulation.ledger();
+        let discrepancy = ledger.discrepancy();
+        if discrepancy.abs() > BALANCE_TOLERANCE {
+            println!("Error: the simulated balance doesn't add up");
+            for (label, hours) in [
+                ("opening", ledger.opening),
+                ("+ accrued", ledger.accrued),
+                ("- deducted", ledger.deducted),
+                ("- forfeited", ledger.forfeited),
+                ("- cashed out", ledger.cashed_out),
+                ("+ transferred in", ledger.transferred),
+                ("+ anchors and resets", ledger.anchored),
+                ("closing", ledger.closing),
+                ("discrepancy", discrepancy),
+            ] {
+                println!("  {:<20} {:>10}", label, format!("{:.2}", Num(hours)));
+            }
+            exit(1);
+        }
+        if args.verbose {
+            cprintln!(
+                "<dim>Balance checks out: {:.2} opening to {:.2} closing</dim>",
+                Num(ledger.opening),
+                Num(ledger.closing)
+            );
+        }
+    }
+
+    // Machine outputs are written whatever gets printed below
+    if let Some(path) = &args.status_file
+        && let Err(e) = fs::write(path, status::summary(&simulation, &policy) + "\n")
+    {
+        println!(
+            "Error: could not write status file {}: {}",
+            path.display(),
+            e
+        );
+        exit(1);
+    }
+
+    if let Some(path) = &args.fiscal_summary_csv
+        && let Err(e) = fs::write(path, statement::csv(&simulation))
+    {
+        println!("Error: could not write {}: {}", path.display(), e);
+        exit(1);
+    }
+
+    #[cfg(feature = "webhook")]
+    if let Some(url) = &args.notify_webhook
+        && (!simulation.is_ok()
+            || status::soonest_forfeiture(&simulation, &policy)
+                .is_some_and(|date| (date - today).num_days() <= args.forfeiture_alert_days.into()))
+        && let Err(e) = http::post(url, &status::summary(&simulation, &policy))
+    {
+        warnings::warn(format_args!("webhook notification failed: {}", e));
+    }
+
+    // Everything from here on is for people
+    if args.quiet_if_ok {
+        if simulation.is_ok() {
+            finish(stopwatch, args.benchmark);
+        }
+        print_header(&args, annual_hours, hourly_accrual, holiday_count);
+    }
+
+    // Exact number of accrual credits the simulation lands before each end of the plan
+    let mut milestones = vec![("first", &simulation.outcomes[0])];
+    if let [_, .., last] = simulation.outcomes.as_slice() {
+        milestones.push(("last", last));
+    }
+    for (label, outcome) in milestones {
+        let (count, hours) = simulation.accruals_before(outcome.vacation.start);
+        ceprintln!(
+            "Accruals before {} trip ({}): <blue>{}</blue>, totaling <blue>{:.2}</blue> hours",
+            label,
+            outcome.name,
+            count,
+            Num(hours)
+        );
+    }
+
+    for event in &simulation.events {
+        match &event.kind {
+            EventKind::TransferSkipped { .. } | EventKind::BalanceAnchored => {
+                warnings::warn(event.describe())
+            }
+            EventKind::Deduction { .. } => {}
+            _ if args.verbose => cprintln!("<dim>{}</dim>", event.describe()),
+            _ => {}
+        }
+    }
+
+    if let Some(date) = args.explain_date {
+        let Some(next) = date.succ_opt() else {
+            println!("Error: {}", sim::too_far_out(date));
+            exit(1);
+        };
+        cprintln!(
+            "<blue>Projected PTO balance on {}: {:.2} hours</blue>",
+            date,
+            Num(simulation.balance_on(date))
+        );
+        let (count, hours) = simulation.accruals_before(next);
+        println!(
+            "Starting balance on {}: {:.2} hours",
+            today,
+            Num(policy.bank)
+        );
+        println!(
+            "Accruals through {}: {}, totaling {:.2} hours",
+            date,
+            count,
+            Num(hours)
+        );
+        for event in simulation.events.iter().take_while(|e| e.date <= date) {
+            println!("{}", event.describe());
+        }
+        finish(stopwatch, args.benchmark);
+    }
+
+    if let Some(trip) = &args.explain_affordability {
+        let found = analysis::find_trip(&vacations, trip).and_then(|i| simulation.outcomes.get(i));
+        let Some(outcome) = found else {
+            println!("Error: no vacation named or numbered '{}'", trip);
+            exit(1);
+        };
+        explain_affordability(&simulation, &policy, outcome);
+        finish(stopwatch, args.benchmark);
+    }
+
+    if let Some(what_if) = &args.move_trip {
+        let Some(index) = analysis::find_trip(&vacations, &what_if.trip) else {
+            println!("Error: no vacation named or numbered '{}'", what_if.trip);
+            exit(1);
+        };
+        if what_if.start <= today {
+            println!(
+                "Error: can't move a trip to {}, which has passed",
+                what_if.start
+            );
+            exit(1);
+        }
+        let mut moved = vacations.clone();
+        let trip = &mut moved[index];
+        let Some(end) = what_if.start.checked_add_signed(trip.end - trip.start) else {
+            println!("Error: {}", sim::too_far_out(what_if.start));
+            exit(1);
+        };
+        trip.end = end;
+        trip.start = what_if.start;
+        cprintln!(
+            "Moving <blue>{}</blue> to {} – {}",
+            simulation.outcomes[index].name,
+            trip.start,
+            trip.end
+        );
+        // Remember where each trip came from once the moved one re-sorts
+        let mut order: Vec<usize> = (0..moved.len()).collect();
+        order.sort_by(|a, b| moved[*a].sort_key().cmp(&moved[*b].sort_key()));
+        let moved: Vec<_> = order.iter().map(|i| moved[*i].clone()).collect();
+        let after = match sim::simulate(today, &moved, &policy) {
+            Ok(after) => after,
+            Err(e) => {
+                println!("Error: {}", e);
+                exit(1);
+            }
+        };
+        for (outcome, original) in after.outcomes.iter().zip(&order) {
+            let before = &simulation.outcomes[*original];
+            if *original == index || before.status != outcome.status {
+                println!(
+                    "{}: {} → {}",
+                    outcome.name,
+                    before.status.glyph(),
+                    outcome.status.glyph()
+                );
+            }
+        }
+        cprintln!(
+            "<blue>Final PTO balance: {:.2} → {:.2} hours</blue>",
+            Num(simulation.final_balance),
+            Num(after.final_balance)
+        );
+        finish(stopwatch, args.benchmark);
+    }
+
+    if args.badge {
+        let svg = badge::svg(&simulation);
+        match &args.output {
+            Some(path) => {
+                if let Err(e) = fs::write(path, svg) {
+                    println!("Error: could not write {}: {}", path.display(), e);
+                    exit(1);
+                }
+            }
+            None => print!("{}", svg),
+        }
+        finish(stopwatch, args.benchmark);
+    }
+
+    if args.format == Format::Prometheus {
+        print!("{}", metrics::render(&simulation));
+        finish(stopwatch, args.benchmark);
     }
 
     // Print the formatted table
-    let table = Table::new(rows);
-    println!("{}", table);
-    cprintln!("\n<blue>Final PTO balance: {:.2} hours</blue>", pto_balance);
+    let style = table::Style {
+        icons: args.icons && !args.ascii && !args.no_color,
+        pretty_dates_from: args.pretty_dates.then_some(today),
+    };
+    let mut columns = args
+        .columns
+        .clone()
+        .unwrap_or_else(|| Column::defaults(&simulation.outcomes));
+    if args.show_accrual_offset && !columns.contains(&Column::Accrued) {
+        columns.push(Column::Accrued);
+    }
+    match args.group_by {
+        Some(GroupBy::Category) => {
+            for (category, outcomes) in table::by_category(&simulation.outcomes) {
+                cprintln!("\n<blue>{}</blue>", category);
+                println!(
+                    "{}",
+                    table::vacations(outcomes.iter().copied(), &columns, style)
+                );
+                println!("Subtotal: {}", table::subtotal(&outcomes));
+                let last = outcomes.last().unwrap();
+                let balance = Balance(simulation.balance_on(last.vacation.start));
+                cprintln!(
+                    "<blue>PTO balance after last {} trip: {:.2} hours{}</blue>",
+                    category,
+                    balance,
+                    balance.owed()
+                );
+            }
+        }
+        None if args.show_interim => {
+            println!("{}", table::with_interim(&simulation, &columns, style))
+        }
+        None => println!(
+            "{}",
+            table::vacations(&simulation.outcomes, &columns, style)
+        ),
+    }
+    if args.calendar {
+        let style = calendar::Style {
+            color: !args.no_color,
+            ascii: args.ascii,
+            today,
+        };
+        println!(
+            "\n{}",
+            calendar::render(&simulation.outcomes, &policy, style)
+        );
+    }
+    cprintln!(
+        "\n<blue>Final {}PTO balance: {:.2} hours{}</blue>",
+        if args.household.is_empty() {
+            ""
+        } else {
+            "shared "
+        },
+        Balance(simulation.final_balance),
+        Balance(simulation.final_balance).owed()
+    );
+    for (bank, hours) in &simulation.other_banks {
+        cprintln!(
+            "<blue>Final {} balance: {:.2} hours{}</blue>",
+            bank,
+            Balance(*hours),
+            Balance(*hours).owed()
+        );
+    }
+    if args.health_score {
+        cprintln!(
+            "<blue>Plan health: {:.0}/100</blue>",
+            Num(health::score(&simulation.outcomes))
+        );
+        if args.verbose {
+            let penalties = health::penalties(&simulation.outcomes);
+            for (outcome, penalty) in simulation.outcomes.iter().zip(penalties) {
+                cprintln!(
+                    "<dim>{} (priority {}): {:.1} points</dim>",
+                    outcome.name,
+                    Num(health::weight(outcome)),
+                    // Adding zero keeps a zero penalty from showing as -0.0
+                    Num(-penalty + 0.0)
+                );
+            }
+        }
+    }
+    if let Some(budget) = policy.budget {
+        let planned: f32 = simulation
+            .outcomes
+            .iter()
+            .filter(|o| o.status == sim::Status::Affordable)
+            .map(|o| o.hours)
+            .sum();
+        cprintln!(
+            "<blue>Planned PTO: {:.2} of {:.2} budgeted hours</blue>",
+            Num(planned),
+            Num(budget)
+        );
+    }
+
+    if args.break_even {
+        match sim::break_even_rate(today, &vacations, &policy) {
+            Some(rate) => {
+                cprintln!(
+                    "\n<blue>You need at least {:.2} hrs/week to afford your entire plan.</blue>",
+                    Num(rate)
+                );
+                if policy.accrual_per_week >= rate {
+                    println!(
+                        "Your current {} hrs/week already suffices.",
+                        Num(policy.accrual_per_week)
+                    );
+                } else {
+                    println!(
+                        "That's {:.2} hrs/week more than your current {}.",
+                        Num(rate - policy.accrual_per_week),
+                        Num(policy.accrual_per_week)
+                    );
+                }
+            }
+            None => {
+                warnings::warn(
+                    "no accrual rate makes every trip affordable; some come too soon or hit a cap",
+                );
+                print_never_affordable("Your plan");
+            }
+        }
+    }
+
+    if args.verify_against.is_some() {
+        let skipped = snapshots.iter().filter(|s| s.date < today).count();
+        if skipped > 0 {
+            ceprintln!(
+                "<dim>Skipped {} snapshot(s) dated before today</dim>",
+                skipped
+            );
+        }
+        let mut builder = tabled::builder::Builder::default();
+        builder.push_record(["Date", "Projected", "Actual", "Delta", ""]);
+        for row in reconcile::reconcile(&simulation, &snapshots) {
+            let flag = if row.delta().abs() > args.tolerance {
+                "⚠️"
+            } else {
+                ""
+            };
+            builder.push_record([
+                row.date.to_string(),
+                format!("{:.2}", Num(row.projected)),
+                format!("{:.2}", Num(row.actual)),
+                format!("{:+.2}", Num(row.delta())),
+                flag.to_string(),
+            ]);
+        }
+        cprintln!("\n<blue>Reconciliation against actual balances:</blue>");
+        println!("{}", builder.build());
+    }
+
+    if args.detect_gaps {
+        let gaps = analysis::gaps(today, &vacations);
+        cprintln!("\n<blue>Longest stretches without a vacation:</blue>");
+        for gap in gaps.iter().take(GAPS_SHOWN) {
+            println!("  {} to {}: {} days", gap.from, gap.to, gap.days());
+        }
+        if let Some(max) = config.max_gap_days {
+            for gap in gaps.iter().filter(|g| g.days() > max) {
+                warnings::warn(format_args!(
+                    "{} days without a break from {} to {} (max {})",
+                    gap.days(),
+                    gap.from,
+                    gap.to,
+                    max
+                ));
+            }
+        }
+    }
+
+    // Advisory only: HR may flag balances that sit too high
+    if let Some(threshold) = config.audit_threshold {
+        let periods = simulation.periods_above(threshold);
+        if !periods.is_empty() {
+            let (peak_date, peak) = simulation.peak();
+            warnings::warn(format_args!(
+                "balance exceeds the audit threshold of {:.2} hours, peaking at {:.2} hours on {}",
+                Num(threshold),
+                Num(peak),
+                peak_date
+            ));
+            for (from, until) in periods {
+                match until {
+                    Some(until) => warnings::detail(format_args!(
+                        "above threshold from {} until {}",
+                        from, until
+                    )),
+                    None => warnings::detail(format_args!("above threshold from {} onward", from)),
+                }
+            }
+        }
+    }
+
+    // Planned PTO days per year against a flat annual allowance
+    if let Some(allowance) = config.annual_allowance {
+        let mut used: BTreeMap<i32, f32> = BTreeMap::new();
+        for outcome in &simulation.outcomes {
+            for (date, charge) in sim::pto_charges(&outcome.vacation, &policy) {
+                *used.entry(date.year()).or_default() += charge;
+            }
+        }
+        for (year, days) in used {
+            let remaining = allowance - days;
+            if remaining < 0.0 {
+                warnings::warn(format_args!(
+                    "{} plan uses {} PTO days, {} over the {} day allowance",
+                    year,
+                    Num(days),
+                    Num(-remaining),
+                    Num(allowance)
+                ));
+            } else {
+                cprintln!(
+                    "<blue>{} allowance: {} of {} days used, {} remaining</blue>",
+                    year,
+                    Num(days),
+                    Num(allowance),
+                    Num(remaining)
+                );
+            }
+        }
+    }
+
+    if let Some(end) = policy.employment_end {
+        for outcome in &simulation.outcomes {
+            if outcome.status == sim::Status::AfterEmployment {
+                warnings::warn(format_args!(
+                    "{} starts after employment ends on {}",
+                    outcome.name, end
+                ));
+            }
+        }
+    }
+
+    if let Some(date) = args.payout_on {
+        let balance = simulation.balance_on(date).max(0.0);
+        let paid = config
+            .max_payout_hours
+            .map_or(balance, |max| balance.min(max));
+        cprintln!("<blue>Payout on {}: {:.2} hours</blue>", date, Num(paid));
+        if paid < balance {
+            cprintln!(
+                "<dim>{:.2} hours over the {:.2} hour payout cap are lost</dim>",
+                Num(balance - paid),
+                Num(paid)
+            );
+        }
+        if let Some(rate) = config.hourly_rate {
+            cprintln!("<blue>Payout value: {:.2}</blue>", Num(paid * rate));
+        }
+    }
+
+    if let Some(max) = policy.max_pto_days_per_trip {
+        for outcome in &simulation.outcomes {
+            if outcome.status == sim::Status::TooLong {
+                warnings::warn(format_args!(
+                    "{} needs {} PTO days, over the {} day limit per trip",
+                    outcome.name,
+                    Num(outcome.days),
+                    Num(max)
+                ));
+            }
+        }
+    }
+
+    // Trips coming up soon that still need flights, a hotel or sign-off
+    for outcome in &simulation.outcomes {
+        let Some(booked) = outcome.vacation.booked else {
+            continue;
+        };
+        let days_until = (outcome.vacation.start - today).num_days();
+        if (0..=IMMINENT_DAYS).contains(&days_until) && !booked.missing().is_empty() {
+            warnings::warn(format_args!(
+                "{} starts in {} days but is only {} booked (missing: {})",
+                outcome.name,
+                days_until,
+                booked.summary(),
+                booked.missing().join(", ")
+            ));
+        }
+    }
+
+    if let Some(cash_out) = policy.cash_out {
+        let mut paid: BTreeMap<i32, f32> = BTreeMap::new();
+        for event in &simulation.events {
+            if event.kind == EventKind::CashOut {
+                *paid.entry(event.date.year()).or_default() += event.hours;
+            }
+        }
+        for (year, hours) in paid {
+            match cash_out.hourly_rate {
+                Some(rate) => cprintln!(
+                    "<blue>{} cash-out: {:.2} hours, worth {:.2}</blue>",
+                    year,
+                    Num(hours),
+                    Num(hours * rate)
+                ),
+                None => cprintln!("<blue>{} cash-out: {:.2} hours</blue>", year, Num(hours)),
+            }
+        }
+    }
+
+    // Whatever is left above the carryover cap at year end is lost
+    if let Some(cap) = policy.carryover_cap
+        && let Some(year_end) = policy.carryover_year_end(today)
+    {
+        let balance = simulation.balance_on(year_end);
+        let surplus = (balance - cap).max(0.0);
+        cprintln!(
+            "<blue>Spend-or-lose before your anniversary: {:.2} hours</blue> <dim>(projected balance {:.2} on {}, carryover cap {:.2})</dim>",
+            Num(surplus),
+            Num(balance),
+            year_end,
+            Num(cap)
+        );
+    } else if let Some(cap) = policy.carryover_cap
+        && let Some(year_end) = simulation.year_ends.first()
+    {
+        let surplus = (year_end.balance - cap).max(0.0);
+        cprintln!(
+            "<blue>Spend-or-lose this year: {:.2} hours</blue> <dim>(projected {} year-end balance {:.2}, carryover cap {:.2})</dim>",
+            Num(surplus),
+            year_end.year,
+            Num(year_end.balance),
+            Num(cap)
+        );
+    }
+
+    finish(stopwatch, args.benchmark);
 }
@@ -0,0 +1,97 @@
+//! Prometheus text exposition of a run, for `--format prometheus`. Metric
+//! names and labels are part of the interface; don't rename them.
+//!
+//! - `vacay_final_balance_hours`: projected primary balance after the plan
+//! - `vacay_unaffordable_trips_total`: trips that aren't affordable, i.e.
+//!   those with `vacay_trip_affordable` 0
+//! - `vacay_soonest_shortfall_days`: days until the first unaffordable trip,
+//!   absent without a shortfall
+//! - `vacay_trip_hours{trip, start}`: PTO hours each trip needs
+//! - `vacay_trip_affordable{trip, start}`: 1 if the trip is affordable, else 0
+
+use crate::sim::Simulation;
+use std::fmt::Write;
+
+fn header(out: &mut String, name: &str, help: &str) {
+    writeln!(out, "# HELP {} {}", name, help).unwrap();
+    writeln!(out, "# TYPE {} gauge", name).unwrap();
+}
+
+/// Escape a label value per the text format: backslash, quote and newline.
+fn label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+pub fn render(simulation: &Simulation) -> String {
+    let mut out = String::new();
+    let shortfalls: Vec<_> = simulation
+        .outcomes
+        .iter()
+        .filter(|o| !o.affordable())
+        .collect();
+
+    header(
+        &mut out,
+        "vacay_final_balance_hours",
+        "Projected PTO balance after the last planned event, in hours.",
+    );
+    writeln!(
+        out,
+        "vacay_final_balance_hours {}",
+        simulation.final_balance
+    )
+    .unwrap();
+
+    header(
+        &mut out,
+        "vacay_unaffordable_trips_total",
+        "Planned trips that aren't affordable.",
+    );
+    writeln!(out, "vacay_unaffordable_trips_total {}", shortfalls.len()).unwrap();
+
+    header(
+        &mut out,
+        "vacay_soonest_shortfall_days",
+        "Days from today until the first unaffordable trip starts.",
+    );
+    if let Some(soonest) = shortfalls.first() {
+        let days = (soonest.vacation.start - simulation.today).num_days();
+        writeln!(out, "vacay_soonest_shortfall_days {}", days).unwrap();
+    }
+
+    header(
+        &mut out,
+        "vacay_trip_hours",
+        "PTO hours a planned trip needs.",
+    );
+    for outcome in &simulation.outcomes {
+        writeln!(
+            out,
+            "vacay_trip_hours{{trip=\"{}\",start=\"{}\"}} {}",
+            label(&outcome.name),
+            outcome.vacation.start,
+            outcome.hours
+        )
+        .unwrap();
+    }
+
+    header(
+        &mut out,
+        "vacay_trip_affordable",
+        "Whether the projected balance covers a planned trip (1) or not (0).",
+    );
+    for outcome in &simulation.outcomes {
+        writeln!(
+            out,
+            "vacay_trip_affordable{{trip=\"{}\",start=\"{}\"}} {}",
+            label(&outcome.name),
+            outcome.vacation.start,
+            u8::from(outcome.affordable())
+        )
+        .unwrap();
+    }
+    out
+}
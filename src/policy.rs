@@ -0,0 +1,61 @@
+//! Company policy fetched from a shared URL (the `policy-url` feature). The
+//! document is TOML in the same shape as a config file and is cached so
+//! repeated runs don't hit the endpoint.
+
+use crate::http;
+use std::collections::hash_map::DefaultHasher;
+use std::env;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+fn cache_path(url: &str) -> Option<PathBuf> {
+    let dir = env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    let mut hasher = DefaultHasher::new();
+    url.hash(&mut hasher);
+    Some(
+        dir.join("vacay")
+            .join(format!("policy-{:016x}.toml", hasher.finish())),
+    )
+}
+
+fn read_cache(url: &str, ttl: Option<Duration>) -> Option<String> {
+    let path = cache_path(url)?;
+    if let Some(ttl) = ttl {
+        let age = fs::metadata(&path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())?;
+        if age > ttl {
+            return None;
+        }
+    }
+    fs::read_to_string(path).ok()
+}
+
+/// The policy document: from the cache if it's younger than `ttl`, otherwise
+/// freshly fetched. If the fetch fails, a stale cached copy is used instead;
+/// the error is only returned when there's nothing cached at all.
+pub fn fetch(url: &str, ttl: Duration) -> Result<String, String> {
+    if let Some(cached) = read_cache(url, Some(ttl)) {
+        return Ok(cached);
+    }
+    match http::get(url) {
+        Ok(body) => {
+            toml::from_str::<toml::Table>(&body)
+                .map_err(|e| format!("policy from {} is not valid TOML: {}", url, e))?;
+            // Caching is best effort; a failed write just means fetching again next time
+            if let Some(path) = cache_path(url)
+                && let Some(dir) = path.parent()
+                && fs::create_dir_all(dir).is_ok()
+            {
+                let _ = fs::write(path, &body);
+            }
+            Ok(body)
+        }
+        Err(e) => read_cache(url, None).ok_or(e),
+    }
+}
@@ -0,0 +1,68 @@
+//! Compare the projection with actual balances exported from payroll.
+
+use crate::csv;
+use crate::sim::Simulation;
+use chrono::NaiveDate;
+use std::fs;
+use std::path::Path;
+
+/// An actual balance on a date.
+pub struct Snapshot {
+    pub date: NaiveDate,
+    pub balance: f32,
+}
+
+/// Read `date,balance` rows. A header row is optional; when present the
+/// columns are found by name, otherwise the first two columns are used.
+pub fn load(path: &Path) -> Result<Vec<Snapshot>, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+    let mut records = csv::parse(&text).into_iter().peekable();
+
+    let (mut date_col, mut balance_col) = (0, 1);
+    if let Some(header) =
+        records.next_if(|r| r.get(1).is_some_and(|b| b.trim().parse::<f32>().is_err()))
+    {
+        date_col = csv::column(&header, "date").unwrap_or(0);
+        balance_col = csv::column(&header, "balance").unwrap_or(1);
+    }
+
+    records
+        .enumerate()
+        .map(|(i, record)| {
+            let field = |col: usize| record.get(col).map(|f| f.trim()).unwrap_or_default();
+            let date = NaiveDate::parse_from_str(field(date_col), "%Y-%m-%d")
+                .map_err(|e| format!("{} row {}: bad date: {}", path.display(), i + 1, e))?;
+            let balance = field(balance_col)
+                .parse()
+                .map_err(|e| format!("{} row {}: bad balance: {}", path.display(), i + 1, e))?;
+            Ok(Snapshot { date, balance })
+        })
+        .collect()
+}
+
+pub struct Reconciliation {
+    pub date: NaiveDate,
+    pub projected: f32,
+    pub actual: f32,
+}
+
+impl Reconciliation {
+    pub fn delta(&self) -> f32 {
+        self.actual - self.projected
+    }
+}
+
+/// Line each snapshot up with the projected balance on its date. Snapshots
+/// from before the projection starts can't be compared and are left out.
+pub fn reconcile(simulation: &Simulation, snapshots: &[Snapshot]) -> Vec<Reconciliation> {
+    snapshots
+        .iter()
+        .filter(|s| s.date >= simulation.today)
+        .map(|s| Reconciliation {
+            date: s.date,
+            projected: simulation.balance_on(s.date),
+            actual: s.balance,
+        })
+        .collect()
+}
@@ -0,0 +1,1846 @@
+use crate::config::{FractionScope, Vacation};
+use crate::locale::Num;
+use chrono::{Datelike, NaiveDate, Weekday};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Name of the bank that accrues PTO and pays for vacations.
+pub const PRIMARY_BANK: &str = "pto";
+
+pub const HOURS_PER_DAY: f32 = 8.0;
+/// What a weekly rate is spread over when accrual is per business day.
+const BUSINESS_DAYS_PER_WEEK: f32 = 5.0;
+/// How far past today the simulation is willing to walk.
+const MAX_YEARS_AHEAD: i32 = 100;
+
+/// A scheduled move of hours between two banks, e.g. donating vacation hours
+/// to a shared pool or converting them to sick time.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Transfer {
+    #[serde(with = "toml_datetime_compat")]
+    pub date: NaiveDate,
+    pub from: String,
+    pub to: String,
+    pub hours: f32,
+}
+
+/// Hours taken back outside of any vacation, e.g. correcting an over-grant or
+/// repaying advanced PTO. A clawback may push the balance below zero.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Clawback {
+    #[serde(with = "toml_datetime_compat")]
+    pub date: NaiveDate,
+    pub hours: f32,
+    pub reason: Option<String>,
+}
+
+/// A hard reset of the primary bank to `balance` hours, zero unless given,
+/// e.g. when a plan change wipes and re-grants PTO.
+#[derive(Deserialize, Debug, Clone)]
+pub struct BalanceReset {
+    #[serde(with = "toml_datetime_compat")]
+    pub date: NaiveDate,
+    #[serde(default)]
+    pub balance: f32,
+    pub reason: Option<String>,
+}
+
+/// An inclusive span of dates, e.g. an unpaid sabbatical.
+#[derive(Deserialize, Debug, Clone)]
+pub struct DateRange {
+    #[serde(with = "toml_datetime_compat")]
+    pub start: NaiveDate,
+    #[serde(with = "toml_datetime_compat")]
+    pub end: NaiveDate,
+}
+
+impl DateRange {
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.start <= date && date <= self.end
+    }
+}
+
+/// A stretch worked at a fraction of full time, e.g. `0.5` for half time.
+/// Accrual in the range is scaled by the fraction.
+#[derive(Deserialize, Debug, Clone)]
+pub struct WorkPeriod {
+    #[serde(with = "toml_datetime_compat")]
+    pub start: NaiveDate,
+    #[serde(with = "toml_datetime_compat")]
+    pub end: NaiveDate,
+    pub fraction: f32,
+}
+
+/// How a vacation category counts PTO days; unset rules fall back to the
+/// global ones.
+#[derive(Debug, Clone, Default)]
+pub struct CategoryRules {
+    /// Weekdays that cost PTO, e.g. all seven for sick leave.
+    pub workdays: Option<Vec<Weekday>>,
+    /// Holidays that cost no PTO, replacing the global list.
+    pub holidays: Option<Vec<NaiveDate>>,
+}
+
+/// A new weekly accrual rate taking effect on a date.
+#[derive(Debug, Clone, Copy)]
+pub struct RateChange {
+    pub date: NaiveDate,
+    pub rate: f32,
+}
+
+/// Parse a `--simulate-raise` argument of the form `YYYY-MM-DD=RATE`.
+pub fn parse_rate_change(arg: &str) -> Result<RateChange, String> {
+    let (date, rate) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("expected YYYY-MM-DD=RATE, got '{}'", arg))?;
+    let date = NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+        .map_err(|e| format!("invalid date '{}': {}", date, e))?;
+    let rate = rate
+        .trim()
+        .parse()
+        .map_err(|e| format!("invalid rate '{}': {}", rate, e))?;
+    Ok(RateChange { date, rate })
+}
+
+/// A known primary bank balance on a date, e.g. from a mid-year statement.
+#[derive(Debug, Clone, Copy)]
+pub struct BalanceAnchor {
+    pub date: NaiveDate,
+    pub balance: f32,
+}
+
+/// Parse a `--assume-balance-at` argument of the form `YYYY-MM-DD=HOURS`.
+pub fn parse_balance_anchor(arg: &str) -> Result<BalanceAnchor, String> {
+    let (date, balance) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("expected YYYY-MM-DD=HOURS, got '{}'", arg))?;
+    let date = NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+        .map_err(|e| format!("invalid date '{}': {}", date, e))?;
+    let balance = balance
+        .trim()
+        .parse()
+        .map_err(|e| format!("invalid balance '{}': {}", balance, e))?;
+    Ok(BalanceAnchor { date, balance })
+}
+
+/// What happens to accrual that would push the balance over the cap.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CapOverflowMode {
+    /// The excess is lost.
+    #[default]
+    Drop,
+    /// The excess waits and is credited as soon as spending makes room under
+    /// the cap. Anything still waiting at year end is lost.
+    Queue,
+}
+
+/// The first days of employment, during which nothing accrues.
+#[derive(Debug, Clone, Copy)]
+pub struct Probation {
+    pub hire_date: NaiveDate,
+    /// First day after probation; accrual resumes here.
+    pub end: NaiveDate,
+    /// Credit what probation would have accrued in one lump on `end`,
+    /// rather than never.
+    pub retroactive: bool,
+}
+
+/// A year's PTO credited at once, on the same day every year.
+#[derive(Debug, Clone, Copy)]
+pub struct AnnualGrant {
+    pub hours: f32,
+    /// Any date whose month and day the grant lands on: January 1, or the
+    /// hire date for anniversary grants.
+    pub on: NaiveDate,
+}
+
+impl AnnualGrant {
+    /// Whether the grant lands on `date`. A February 29 anniversary lands
+    /// on February 28 in other years.
+    fn lands_on(self, date: NaiveDate) -> bool {
+        let (month, day) = (self.on.month(), self.on.day());
+        if month == 2 && day == 29 && !date.leap_year() {
+            return date.month() == 2 && date.day() == 28;
+        }
+        date.month() == month && date.day() == day
+    }
+}
+
+/// How a vacation starting on `today` is treated.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum StartingToday {
+    /// Not yet taken: deducted from the bank in full, like any later trip.
+    #[default]
+    Future,
+    /// Already under way: the bank is taken to reflect it, so it's shown as
+    /// affordable but not deducted again.
+    InProgress,
+}
+
+/// How a partial holiday combines with the PTO a vacation day would cost.
+/// With `charge` the day's cost (1, or the trip's `fraction`) and `off` the
+/// holiday's share of the day:
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PartialHolidayRule {
+    /// `max(charge - off, 0)`: a full day on a half holiday costs 0.5, a
+    /// half day on one costs nothing.
+    #[default]
+    Subtract,
+    /// `charge × (1 - off)`: a full day on a half holiday costs 0.5, a half
+    /// day on one 0.25.
+    Scale,
+    /// Any holiday, however partial, costs no PTO.
+    Free,
+}
+
+impl PartialHolidayRule {
+    fn apply(self, charge: f32, off: f32) -> f32 {
+        match self {
+            PartialHolidayRule::Subtract => (charge - off).max(0.0),
+            PartialHolidayRule::Scale => charge * (1.0 - off),
+            PartialHolidayRule::Free => 0.0,
+        }
+    }
+}
+
+/// Which way each accrual credit is rounded to the `accrualRounding` step.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RoundingMode {
+    Floor,
+    Ceil,
+    #[default]
+    Nearest,
+}
+
+impl RoundingMode {
+    /// `hours` rounded to a multiple of `step` in this direction.
+    pub fn round(self, hours: f32, step: f32) -> f32 {
+        // Payroll steps like 0.01 aren't exact in binary; nudge away from the
+        // float error so 2.3 / 0.1 doesn't floor to 22
+        let steps = hours / step;
+        let steps = match self {
+            RoundingMode::Floor => (steps + 1e-4).floor(),
+            RoundingMode::Ceil => (steps - 1e-4).ceil(),
+            RoundingMode::Nearest => steps.round(),
+        };
+        steps * step
+    }
+}
+
+/// Paying out hours above a threshold at the end of each period.
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub struct CashOut {
+    pub threshold: f32,
+    #[serde(default)]
+    pub frequency: CashOutFrequency,
+    /// Pay per PTO hour, to show what a cash-out is worth.
+    pub hourly_rate: Option<f32>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CashOutFrequency {
+    #[default]
+    Yearly,
+    Quarterly,
+    Monthly,
+}
+
+impl CashOutFrequency {
+    /// Whether a period ends at the close of `date`.
+    fn ends_period(self, date: NaiveDate) -> bool {
+        let month_end = date.succ_opt().is_none_or(|next| next.day() == 1);
+        match self {
+            CashOutFrequency::Yearly => month_end && date.month() == 12,
+            CashOutFrequency::Quarterly => month_end && date.month().is_multiple_of(3),
+            CashOutFrequency::Monthly => month_end,
+        }
+    }
+}
+
+/// An accrual on top of the weekly rate, e.g. a wellness benefit.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AccrualSource {
+    pub name: String,
+    /// Hours credited each time it lands.
+    pub hours: f32,
+    #[serde(default)]
+    pub frequency: AccrualFrequency,
+}
+
+/// When an accrual source lands: Sundays, like the weekly rate, each
+/// business day, or the last day of each month, quarter or year.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AccrualFrequency {
+    #[default]
+    Weekly,
+    /// Every working day that isn't a holiday.
+    #[serde(rename = "perBusinessDay")]
+    PerBusinessDay,
+    Monthly,
+    Quarterly,
+    Yearly,
+}
+
+impl AccrualFrequency {
+    fn lands_on(self, date: NaiveDate, policy: &Policy) -> bool {
+        match self {
+            AccrualFrequency::Weekly => date.weekday() == Weekday::Sun,
+            AccrualFrequency::PerBusinessDay => policy.is_business_day(date),
+            AccrualFrequency::Monthly => CashOutFrequency::Monthly.ends_period(date),
+            AccrualFrequency::Quarterly => CashOutFrequency::Quarterly.ends_period(date),
+            AccrualFrequency::Yearly => CashOutFrequency::Yearly.ends_period(date),
+        }
+    }
+}
+
+/// Everything the simulation needs besides the vacations themselves.
+#[derive(Clone, Default)]
+pub struct Policy {
+    pub bank: f32,
+    pub accrual_per_week: f32,
+    /// Later changes to the weekly rate, each lasting until the next.
+    pub rate_changes: Vec<RateChange>,
+    /// Extra accruals summed with the weekly rate.
+    pub accrual_sources: Vec<AccrualSource>,
+    /// When the weekly rate is credited: all at once on Sundays, or a fifth
+    /// of it each business day, so holidays earn nothing. Only `Weekly` and
+    /// `PerBusinessDay` apply here.
+    pub accrual_frequency: AccrualFrequency,
+    pub holidays: Vec<NaiveDate>,
+    /// Holidays off for only part of the day, by the share off. They are
+    /// working days otherwise, so not in `holidays`.
+    pub partial_holidays: BTreeMap<NaiveDate, f32>,
+    pub partial_holiday_rule: PartialHolidayRule,
+    /// Weekdays never worked, on top of Saturday and Sunday.
+    pub days_off: Vec<Weekday>,
+    /// Day-counting rules that differ by vacation category.
+    pub categories: BTreeMap<String, CategoryRules>,
+    /// Opening balances of banks other than the primary one.
+    pub other_banks: BTreeMap<String, f32>,
+    pub transfers: Vec<Transfer>,
+    /// Let a transfer overdraw its source bank instead of skipping it.
+    pub allow_negative_transfers: bool,
+    /// Most hours that carry into a new year; the rest is forfeited on Jan 1.
+    pub carryover_cap: Option<f32>,
+    /// Highest balance accrual can reach.
+    pub accrual_cap: Option<f32>,
+    pub cap_overflow: CapOverflowMode,
+    /// Payroll rounding of each accrual credit: the step in hours and the
+    /// direction.
+    pub accrual_rounding: Option<(f32, RoundingMode)>,
+    pub probation: Option<Probation>,
+    /// Part-time periods; full time applies outside them.
+    pub work_schedule: Vec<WorkPeriod>,
+    /// Periods with no accrual at all, such as extended unpaid leave.
+    pub no_accrual: Vec<DateRange>,
+    pub clawbacks: Vec<Clawback>,
+    /// Known balances the simulation is reset to on their dates.
+    pub anchors: Vec<BalanceAnchor>,
+    /// Policy resets that replace the balance, applied after any anchor.
+    pub resets: Vec<BalanceReset>,
+    /// Credit an accrual that lands on a vacation's start date before
+    /// deducting the vacation, rather than after.
+    pub accrual_before_deduction: bool,
+    pub starting_today: StartingToday,
+    /// A lump credited yearly; its date also starts the carryover year.
+    pub annual_grant: Option<AnnualGrant>,
+    /// Credit an accrual landing on `today`, rather than taking the bank to
+    /// include it already.
+    pub accrue_today: bool,
+    /// Last day of employment; the simulation stops here.
+    pub employment_end: Option<NaiveDate>,
+    /// Trips needing more PTO days than this are rejected, not deducted.
+    pub max_pto_days_per_trip: Option<f32>,
+    /// Most PTO hours the plan may use in total, whatever the balance.
+    pub budget: Option<f32>,
+    pub cash_out: Option<CashOut>,
+    /// Pay per hour, to value unpaid leave.
+    pub hourly_rate: Option<f32>,
+    /// Keep simulating at least until this date.
+    pub project_until: Option<NaiveDate>,
+    /// Never simulate past this date, whatever else asks to.
+    pub max_horizon: Option<NaiveDate>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EventKind {
+    /// `fraction` is the share of full time the accrual was scaled by.
+    /// `sources` splits it by source when accrual sources are configured.
+    Accrual {
+        during_vacation: bool,
+        fraction: f32,
+        sources: Vec<(String, f32)>,
+    },
+    AccrualSuspended,
+    /// The weekly accrual rate changed; `hours` is the new rate.
+    RateChange,
+    /// Accrual over the cap: dropped, or queued with the new queue total.
+    AccrualCapped {
+        queued: Option<f32>,
+    },
+    QueueReleased {
+        queued: f32,
+    },
+    QueueExpired,
+    Deduction {
+        vacation: String,
+    },
+    Transfer {
+        from: String,
+        to: String,
+    },
+    TransferSkipped {
+        from: String,
+        to: String,
+        available: f32,
+    },
+    Forfeiture,
+    Clawback {
+        reason: Option<String>,
+    },
+    /// The balance was reset to a known value; `hours` is the correction.
+    BalanceAnchored,
+    /// A policy reset replaced the balance; `hours` is the change.
+    BalanceReset {
+        reason: Option<String>,
+    },
+    /// Hours over the cash-out threshold were paid out.
+    CashOut,
+    /// Probation ended and its accrual was credited at once.
+    RetroactiveAccrual,
+    /// The yearly lump grant was credited.
+    Grant,
+}
+
+/// A single change (or attempted change) to a bank, in simulation order.
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub date: NaiveDate,
+    pub kind: EventKind,
+    pub hours: f32,
+    /// Primary bank balance after the event.
+    pub balance: f32,
+}
+
+impl Event {
+    /// One-line, human readable account of the event.
+    pub fn describe(&self) -> String {
+        match &self.kind {
+            EventKind::Accrual {
+                during_vacation,
+                fraction,
+                sources,
+            } => format!(
+                "Accrued PTO {}on {:?}: +{} hours{}{} (balance: {:.2})",
+                if *during_vacation {
+                    "during vacation "
+                } else {
+                    ""
+                },
+                self.date,
+                Num(self.hours),
+                if *fraction < 1.0 {
+                    format!(" at {}% of full time", Num(fraction * 100.0))
+                } else {
+                    String::new()
+                },
+                if sources.is_empty() {
+                    String::new()
+                } else {
+                    let parts: Vec<String> = sources
+                        .iter()
+                        .map(|(name, hours)| format!("{} {}", name, Num(*hours)))
+                        .collect();
+                    format!(" ({})", parts.join(", "))
+                },
+                Num(self.balance)
+            ),
+            EventKind::Grant => format!(
+                "Granted annual PTO on {:?}: +{:.2} hours (balance: {:.2})",
+                self.date,
+                Num(self.hours),
+                Num(self.balance)
+            ),
+            EventKind::RetroactiveAccrual => format!(
+                "Credited probation accrual on {:?}: +{:.2} hours (balance: {:.2})",
+                self.date,
+                Num(self.hours),
+                Num(self.balance)
+            ),
+            EventKind::AccrualSuspended => format!(
+                "No accrual on {:?}: accrual suspended (balance: {:.2})",
+                self.date,
+                Num(self.balance)
+            ),
+            EventKind::AccrualCapped { queued: None } => format!(
+                "Dropped accrual over the cap on {:?}: {:.2} hours (balance: {:.2})",
+                self.date,
+                Num(self.hours),
+                Num(self.balance)
+            ),
+            EventKind::AccrualCapped {
+                queued: Some(queued),
+            } => format!(
+                "Queued accrual over the cap on {:?}: {:.2} hours ({:.2} hours queued, balance: {:.2})",
+                self.date,
+                Num(self.hours),
+                Num(*queued),
+                Num(self.balance)
+            ),
+            EventKind::QueueReleased { queued } => format!(
+                "Released queued accrual on {:?}: +{:.2} hours ({:.2} hours still queued, balance: {:.2})",
+                self.date,
+                Num(self.hours),
+                Num(*queued),
+                Num(self.balance)
+            ),
+            EventKind::QueueExpired => format!(
+                "Queued accrual expired at year end on {:?}: {:.2} hours (balance: {:.2})",
+                self.date,
+                Num(self.hours),
+                Num(self.balance)
+            ),
+            EventKind::RateChange => format!(
+                "Accrual rate changed on {:?}: {} hours / week (balance: {:.2})",
+                self.date,
+                Num(self.hours),
+                Num(self.balance)
+            ),
+            EventKind::Deduction { vacation } => format!(
+                "Deducted PTO for {} on {:?}: -{} hours (balance: {:.2})",
+                vacation,
+                self.date,
+                Num(self.hours),
+                Num(self.balance)
+            ),
+            EventKind::Transfer { from, to } => format!(
+                "Transferred {} hours from {} to {} on {:?} (balance: {:.2})",
+                Num(self.hours),
+                from,
+                to,
+                self.date,
+                Num(self.balance)
+            ),
+            EventKind::TransferSkipped {
+                from,
+                to,
+                available,
+            } => format!(
+                "Skipped transfer of {} hours from {} to {} on {:?}; only {:.2} hours available",
+                Num(self.hours),
+                from,
+                to,
+                self.date,
+                Num(*available)
+            ),
+            EventKind::Clawback { reason } => format!(
+                "Clawed back PTO on {:?}: -{} hours, {} (balance: {:.2})",
+                self.date,
+                Num(self.hours),
+                reason.as_deref().unwrap_or("no reason given"),
+                Num(self.balance)
+            ),
+            EventKind::BalanceAnchored => format!(
+                "Balance set to {:.2} hours on {:?}, a correction of {:+.2} hours",
+                Num(self.balance),
+                self.date,
+                Num(self.hours)
+            ),
+            EventKind::BalanceReset { reason } => format!(
+                "Reset PTO on {:?}: {:+.2} hours to {:.2}, {}",
+                self.date,
+                Num(self.hours),
+                Num(self.balance),
+                reason.as_deref().unwrap_or("no reason given")
+            ),
+            EventKind::CashOut => format!(
+                "Cashed out PTO on {:?}: -{:.2} hours (balance: {:.2})",
+                self.date,
+                Num(self.hours),
+                Num(self.balance)
+            ),
+            EventKind::Forfeiture => format!(
+                "Forfeited PTO over the carryover cap on {:?}: -{:.2} hours (balance: {:.2})",
+                self.date,
+                Num(self.hours),
+                Num(self.balance)
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Affordable,
+    Unaffordable,
+    /// Starts after employment ends, so it was never simulated.
+    AfterEmployment,
+    /// Uses more PTO days than a single request may, so it isn't deducted.
+    TooLong,
+    /// Would take planned PTO past the budget, so it isn't deducted.
+    OverBudget,
+    /// Unpaid leave: it costs pay instead of PTO, so it always goes ahead.
+    Unpaid,
+}
+
+impl Status {
+    pub fn glyph(self) -> &'static str {
+        match self {
+            Status::Affordable => "✅",
+            Status::Unaffordable => "🚫",
+            Status::AfterEmployment => "⛔ after end",
+            Status::TooLong => "📏 too long",
+            Status::OverBudget => "💸 over budget",
+            Status::Unpaid => "💵 unpaid",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Outcome {
+    pub vacation: Vacation,
+    pub name: String,
+    pub days: f32,
+    pub hours: f32,
+    pub status: Status,
+    /// Primary bank balance when the vacation came up for deduction.
+    pub available: f32,
+    /// Primary bank balance at the end of the vacation's last day, after
+    /// any accrual that landed during it.
+    pub at_return: f32,
+    /// Number of accrual credits landing during the vacation, and their
+    /// total hours.
+    pub accrued_during: (usize, f32),
+    /// Pay an unpaid trip's hours are worth, when the hourly rate is known.
+    pub lost_pay: Option<f32>,
+}
+
+impl Outcome {
+    /// A vacation's PTO cost, with `status` still to be decided.
+    fn new(vacation: &Vacation, policy: &Policy, status: Status, available: f32) -> Self {
+        let days = pto_days(vacation, policy);
+        let hours = days * HOURS_PER_DAY;
+        Outcome {
+            vacation: vacation.clone(),
+            name: vacation.name.as_deref().unwrap_or("Unnamed").to_string(),
+            days,
+            hours,
+            status,
+            available,
+            at_return: available,
+            accrued_during: (0, 0.0),
+            lost_pay: policy
+                .hourly_rate
+                .filter(|_| vacation.unpaid)
+                .map(|rate| hours * rate),
+        }
+    }
+
+    /// Whether the trip goes ahead, paid from PTO or unpaid.
+    pub fn affordable(&self) -> bool {
+        matches!(self.status, Status::Affordable | Status::Unpaid)
+    }
+}
+
+/// Primary bank balance at the close of Dec 31, before any forfeiture.
+#[derive(Debug, Clone, Copy)]
+pub struct YearEnd {
+    pub year: i32,
+    pub balance: f32,
+}
+
+pub struct Simulation {
+    /// The day the simulation starts from.
+    pub today: NaiveDate,
+    pub opening_balance: f32,
+    pub outcomes: Vec<Outcome>,
+    pub events: Vec<Event>,
+    pub final_balance: f32,
+    pub year_ends: Vec<YearEnd>,
+    /// Closing balances of every bank other than the primary one.
+    pub other_banks: BTreeMap<String, f32>,
+    /// Where `max_horizon` cut the simulation short, if it did.
+    pub truncated_at: Option<NaiveDate>,
+}
+
+/// The primary bank's movements over a whole simulation, totalled by kind
+/// from the event log.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Ledger {
+    pub opening: f32,
+    /// Accrual credited, including released queue and probation lumps.
+    pub accrued: f32,
+    /// Vacations and clawbacks.
+    pub deducted: f32,
+    pub forfeited: f32,
+    pub cashed_out: f32,
+    /// Net hours moved in from other banks.
+    pub transferred: f32,
+    /// Net corrections from balance anchors and resets.
+    pub anchored: f32,
+    /// Balance after the last event.
+    pub closing: f32,
+}
+
+impl Ledger {
+    /// Sum up `events` against a bank that started at `opening`.
+    fn tally<'a>(opening: f32, events: impl IntoIterator<Item = &'a Event>) -> Ledger {
+        let mut ledger = Ledger {
+            opening,
+            closing: opening,
+            ..Ledger::default()
+        };
+        for event in events {
+            ledger.closing = event.balance;
+            match &event.kind {
+                EventKind::Accrual { .. }
+                | EventKind::QueueReleased { .. }
+                | EventKind::RetroactiveAccrual
+                | EventKind::Grant => ledger.accrued += event.hours,
+                EventKind::Deduction { .. } | EventKind::Clawback { .. } => {
+                    ledger.deducted += event.hours
+                }
+                EventKind::Forfeiture => ledger.forfeited += event.hours,
+                EventKind::CashOut => ledger.cashed_out += event.hours,
+                EventKind::Transfer { from, to } => {
+                    if to == PRIMARY_BANK {
+                        ledger.transferred += event.hours;
+                    }
+                    if from == PRIMARY_BANK {
+                        ledger.transferred -= event.hours;
+                    }
+                }
+                EventKind::BalanceAnchored | EventKind::BalanceReset { .. } => {
+                    ledger.anchored += event.hours
+                }
+                // Nothing reached the balance
+                EventKind::AccrualSuspended
+                | EventKind::RateChange
+                | EventKind::AccrualCapped { .. }
+                | EventKind::QueueExpired
+                | EventKind::TransferSkipped { .. } => {}
+            }
+        }
+        ledger
+    }
+
+    /// What the components add up to, less the closing balance the
+    /// simulation reached. Zero, give or take float error, unless the
+    /// accounting is broken.
+    pub fn discrepancy(&self) -> f32 {
+        self.opening + self.accrued - self.deducted - self.forfeited - self.cashed_out
+            + self.transferred
+            + self.anchored
+            - self.closing
+    }
+}
+
+impl Simulation {
+    /// Total up the event log into a ledger of the primary bank.
+    pub fn ledger(&self) -> Ledger {
+        Ledger::tally(self.opening_balance, &self.events)
+    }
+
+    /// One ledger per calendar year from `today` to the last event, each
+    /// opening on the previous one's closing balance.
+    pub fn yearly_ledgers(&self) -> Vec<(i32, Ledger)> {
+        let last_year = self.events.last().map_or(self.today, |e| e.date).year();
+        let mut opening = self.opening_balance;
+        (self.today.year()..=last_year)
+            .map(|year| {
+                let events: Vec<&Event> = self
+                    .events
+                    .iter()
+                    .filter(|e| e.date.year() == year)
+                    .collect();
+                let ledger = Ledger::tally(opening, events);
+                opening = ledger.closing;
+                (year, ledger)
+            })
+            .collect()
+    }
+
+    /// Whether every vacation goes ahead as planned. This is what "ok" means
+    /// for `--quiet-if-ok`, the status file and the webhook.
+    pub fn is_ok(&self) -> bool {
+        self.outcomes.iter().all(Outcome::affordable)
+    }
+
+    /// Whether any vacation can't be covered by the balance.
+    pub fn has_shortfall(&self) -> bool {
+        self.outcomes
+            .iter()
+            .any(|o| o.status == Status::Unaffordable)
+    }
+
+    /// Number of accrual credits landing strictly before `date`, and their
+    /// total hours.
+    pub fn accruals_before(&self, date: NaiveDate) -> (usize, f32) {
+        self.accruals_in(|d| d < date)
+    }
+
+    /// Number of accrual credits on dates matching `when`, and their total.
+    fn accruals_in(&self, when: impl Fn(NaiveDate) -> bool) -> (usize, f32) {
+        self.events
+            .iter()
+            .filter(|e| when(e.date) && matches!(e.kind, EventKind::Accrual { .. }))
+            .fold((0, 0.0), |(count, hours), e| (count + 1, hours + e.hours))
+    }
+
+    /// Primary bank balance after each event, starting with the opening
+    /// balance on `today`.
+    fn balances(&self) -> impl Iterator<Item = (NaiveDate, f32)> {
+        std::iter::once((self.today, self.opening_balance))
+            .chain(self.events.iter().map(|e| (e.date, e.balance)))
+    }
+
+    /// The highest projected balance and the first date it's reached.
+    pub fn peak(&self) -> (NaiveDate, f32) {
+        self.balances()
+            .fold((self.today, f32::MIN), |peak, (date, balance)| {
+                if balance > peak.1 {
+                    (date, balance)
+                } else {
+                    peak
+                }
+            })
+    }
+
+    /// Stretches where the balance is above `threshold`: the date it rises
+    /// above, and the date it drops back (if it does).
+    pub fn periods_above(&self, threshold: f32) -> Vec<(NaiveDate, Option<NaiveDate>)> {
+        let mut periods: Vec<(NaiveDate, Option<NaiveDate>)> = Vec::new();
+        let mut above = false;
+        for (date, balance) in self.balances() {
+            if balance > threshold && !above {
+                periods.push((date, None));
+            } else if balance <= threshold
+                && above
+                && let Some(period) = periods.last_mut()
+            {
+                period.1 = Some(date);
+            }
+            above = balance > threshold;
+        }
+        periods
+    }
+
+    /// Primary bank balance at the close of `date`.
+    pub fn balance_on(&self, date: NaiveDate) -> f32 {
+        self.events
+            .iter()
+            .take_while(|e| e.date <= date)
+            .last()
+            .map_or(self.opening_balance, |e| e.balance)
+    }
+}
+
+/// The dates a vacation costs PTO: working days in the inclusive range that
+/// aren't holidays, by the rules of the vacation's category if it has any.
+pub fn pto_dates<'a>(
+    vacation: &'a Vacation,
+    policy: &'a Policy,
+) -> impl Iterator<Item = NaiveDate> + 'a {
+    let rules = vacation
+        .category
+        .as_ref()
+        .and_then(|category| policy.categories.get(category));
+    let workdays = rules.and_then(|r| r.workdays.as_ref());
+    let holidays = rules
+        .and_then(|r| r.holidays.as_ref())
+        .unwrap_or(&policy.holidays);
+    vacation
+        .start
+        .iter_days()
+        .take_while(|date| *date <= vacation.end)
+        .filter(move |date| match workdays {
+            Some(workdays) => workdays.contains(&date.weekday()),
+            None => !policy.is_day_off(*date),
+        })
+        .filter(|date| !holidays.contains(date))
+}
+
+/// The PTO charged for each day of a vacation, as a share of a full day.
+///
+/// Days off and whole holidays cost nothing. Every other day costs a full
+/// day, scaled by the trip's `fraction` where it applies; a partial holiday
+/// then reduces that by `partial_holiday_rule`. A category with its own
+/// holiday list ignores partial holidays, like the global list.
+pub fn pto_charges(vacation: &Vacation, policy: &Policy) -> Vec<(NaiveDate, f32)> {
+    let mut charges: Vec<(NaiveDate, f32)> = pto_dates(vacation, policy)
+        .map(|date| (date, 1.0))
+        .collect();
+    if let Some(fraction) = vacation.fraction {
+        let scaled = match vacation.fraction_applies_to {
+            FractionScope::All => &mut charges[..],
+            FractionScope::Start => charges
+                .first_mut()
+                .map(std::slice::from_mut)
+                .unwrap_or_default(),
+            FractionScope::End => charges
+                .last_mut()
+                .map(std::slice::from_mut)
+                .unwrap_or_default(),
+        };
+        for (_, charge) in scaled {
+            *charge = fraction;
+        }
+    }
+    let own_holidays = vacation
+        .category
+        .as_ref()
+        .and_then(|category| policy.categories.get(category))
+        .is_some_and(|rules| rules.holidays.is_some());
+    if !own_holidays {
+        for (date, charge) in &mut charges {
+            if let Some(off) = policy.partial_holidays.get(date) {
+                *charge = policy.partial_holiday_rule.apply(*charge, *off);
+            }
+        }
+    }
+    charges
+}
+
+/// Count the PTO days a vacation needs.
+pub fn pto_days(vacation: &Vacation, policy: &Policy) -> f32 {
+    pto_charges(vacation, policy).iter().map(|(_, c)| c).sum()
+}
+
+/// State of one simulation: the running balances and the log of everything
+/// that touched them.
+struct Run<'a> {
+    today: NaiveDate,
+    vacations: &'a [Vacation],
+    policy: &'a Policy,
+    balance: f32,
+    /// Over-cap accrual waiting for room under the cap.
+    queued: f32,
+    /// PTO hours deducted for vacations so far, against `budget`.
+    planned: f32,
+    /// Set once a vacation didn't fit the budget; every later one is over too.
+    over_budget: bool,
+    other_banks: BTreeMap<String, f32>,
+    events: Vec<Event>,
+}
+
+impl Policy {
+    /// A working day that isn't a holiday.
+    pub fn is_business_day(&self, date: NaiveDate) -> bool {
+        !self.is_day_off(date) && !self.holidays.contains(&date)
+    }
+
+    /// The first business day on or after `date`, or on or before it when
+    /// `forward` is unset. Gives `date` back if there's none within a year.
+    pub fn next_business_day(&self, date: NaiveDate, forward: bool) -> NaiveDate {
+        (0..=366)
+            .map(|offset| {
+                let offset = chrono::Days::new(offset);
+                if forward {
+                    date + offset
+                } else {
+                    date - offset
+                }
+            })
+            .find(|d| self.is_business_day(*d))
+            .unwrap_or(date)
+    }
+
+    /// Weekends and recurring days off.
+    pub fn is_day_off(&self, date: NaiveDate) -> bool {
+        matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+            || self.days_off.contains(&date.weekday())
+    }
+
+    /// The share of full time worked on `date`.
+    pub fn fraction_on(&self, date: NaiveDate) -> f32 {
+        self.work_schedule
+            .iter()
+            .find(|p| p.start <= date && date <= p.end)
+            .map_or(1.0, |p| p.fraction)
+    }
+
+    /// The last day of the carryover year running on `today`: the day
+    /// before the next anniversary grant, with one. `None` without one, as
+    /// the year then simply ends on December 31.
+    pub fn carryover_year_end(&self, today: NaiveDate) -> Option<NaiveDate> {
+        let grant = self.annual_grant?;
+        if grant.on.month() == 1 && grant.on.day() == 1 {
+            return None;
+        }
+        today
+            .iter_days()
+            .skip(1)
+            .take(366)
+            .find(|date| grant.lands_on(*date))?
+            .pred_opt()
+    }
+
+    /// The weekly accrual rate in effect on `date`.
+    pub fn rate_on(&self, date: NaiveDate) -> f32 {
+        self.rate_changes
+            .iter()
+            .filter(|c| c.date <= date)
+            .max_by_key(|c| c.date)
+            .map_or(self.accrual_per_week, |c| c.rate)
+    }
+
+    /// One week's accrual landing on `date`, scaled by the work schedule and
+    /// rounded as payroll would.
+    pub fn accrual_on(&self, date: NaiveDate) -> f32 {
+        self.scaled(self.rate_on(date), date)
+    }
+
+    /// How many accrual credits after `date` it takes to earn `hours`, and
+    /// the day the last of them lands. Suspended accrual earns nothing.
+    /// `None` if the next two years don't earn enough.
+    pub fn credits_to_earn(&self, date: NaiveDate, hours: f32) -> Option<(usize, NaiveDate)> {
+        let mut earned = 0.0;
+        let mut count = 0;
+        for day in date.iter_days().skip(1).take(FLEXIBLE_SEARCH_DAYS as usize) {
+            if self.no_accrual.iter().any(|r| r.contains(day)) {
+                continue;
+            }
+            let credit: f32 = self.credits_on(day).iter().map(|(_, h)| h).sum();
+            if credit > 0.0 {
+                earned += credit;
+                count += 1;
+                if earned >= hours {
+                    return Some((count, day));
+                }
+            }
+        }
+        None
+    }
+
+    /// How accrual credits land, to describe a count of them.
+    pub fn credit_label(&self) -> &'static str {
+        match (self.accrual_frequency, self.accrual_sources.is_empty()) {
+            (AccrualFrequency::PerBusinessDay, true) => "business-day accruals",
+            (_, true) => "weekly accruals",
+            (_, false) => "accrual credits",
+        }
+    }
+
+    /// Every credit landing on `date` by source: the base rate as `base`,
+    /// then each accrual source due that day.
+    fn credits_on(&self, date: NaiveDate) -> Vec<(String, f32)> {
+        let base = match self.accrual_frequency {
+            AccrualFrequency::PerBusinessDay => self.is_business_day(date).then(|| {
+                let weekly = self.rate_on(date) / BUSINESS_DAYS_PER_WEEK;
+                self.scaled(weekly, date)
+            }),
+            _ => (date.weekday() == Weekday::Sun).then(|| self.accrual_on(date)),
+        };
+        let base = base.map(|hours| ("base".to_string(), hours));
+        let sources = self
+            .accrual_sources
+            .iter()
+            .filter(|source| source.frequency.lands_on(date, self))
+            .map(|source| (source.name.clone(), self.scaled(source.hours, date)));
+        // A zero rate, as with a yearly grant instead, credits nothing
+        base.into_iter()
+            .chain(sources)
+            .filter(|(_, hours)| *hours != 0.0)
+            .collect()
+    }
+
+    fn scaled(&self, hours: f32, date: NaiveDate) -> f32 {
+        let accrual = hours * self.fraction_on(date);
+        match self.accrual_rounding {
+            Some((step, mode)) => mode.round(accrual, step),
+            None => accrual,
+        }
+    }
+}
+
+impl Run<'_> {
+    /// Log an event against the primary balance as it now stands.
+    fn record(&mut self, date: NaiveDate, kind: EventKind, hours: f32) {
+        self.events.push(Event {
+            date,
+            kind,
+            hours,
+            balance: self.balance,
+        });
+    }
+
+    /// Hours over the carryover cap, and any queued accrual, are lost as the
+    /// new year starts.
+    fn forfeit(&mut self, date: NaiveDate) {
+        let year_start = match self.policy.annual_grant {
+            Some(grant) => grant.lands_on(date),
+            None => date.ordinal() == 1,
+        };
+        if date > self.today && year_start && self.queued > 0.0 {
+            let expired = self.queued;
+            self.queued = 0.0;
+            self.record(date, EventKind::QueueExpired, expired);
+        }
+        if let Some(cap) = self.policy.carryover_cap
+            && date > self.today
+            && year_start
+            && self.balance > cap
+        {
+            let forfeited = self.balance - cap;
+            self.balance = cap;
+            self.record(date, EventKind::Forfeiture, forfeited);
+        }
+    }
+
+    /// Credit the annual grant on its day, up to the accrual cap.
+    fn grant(&mut self, date: NaiveDate) {
+        let Some(grant) = self.policy.annual_grant else {
+            return;
+        };
+        if date <= self.today || !grant.lands_on(date) {
+            return;
+        }
+        let room = self
+            .policy
+            .accrual_cap
+            .map_or(f32::INFINITY, |cap| (cap - self.balance).max(0.0));
+        let credited = grant.hours.min(room);
+        self.balance += credited;
+        self.record(date, EventKind::Grant, credited);
+    }
+
+    /// Pay out hours over the cash-out threshold as a period closes.
+    fn cash_out(&mut self, date: NaiveDate) {
+        if let Some(cash_out) = self.policy.cash_out
+            && cash_out.frequency.ends_period(date)
+            && self.balance > cash_out.threshold
+        {
+            let paid = self.balance - cash_out.threshold;
+            self.balance = cash_out.threshold;
+            self.record(date, EventKind::CashOut, paid);
+        }
+    }
+
+    fn transfer(&mut self, date: NaiveDate, transfer: &Transfer) {
+        let available = if transfer.from == PRIMARY_BANK {
+            self.balance
+        } else {
+            self.other_banks.get(&transfer.from).copied().unwrap_or(0.0)
+        };
+        let kind = if available >= transfer.hours || self.policy.allow_negative_transfers {
+            for (bank, delta) in [
+                (&transfer.from, -transfer.hours),
+                (&transfer.to, transfer.hours),
+            ] {
+                if bank == PRIMARY_BANK {
+                    self.balance += delta;
+                } else {
+                    *self.other_banks.entry(bank.clone()).or_insert(0.0) += delta;
+                }
+            }
+            EventKind::Transfer {
+                from: transfer.from.clone(),
+                to: transfer.to.clone(),
+            }
+        } else {
+            EventKind::TransferSkipped {
+                from: transfer.from.clone(),
+                to: transfer.to.clone(),
+                available,
+            }
+        };
+        self.record(date, kind, transfer.hours);
+    }
+
+    /// Clawbacks come out regardless of what the balance can cover.
+    fn claw_back(&mut self, date: NaiveDate, clawback: &Clawback) {
+        self.balance -= clawback.hours;
+        let kind = EventKind::Clawback {
+            reason: clawback.reason.clone(),
+        };
+        self.record(date, kind, clawback.hours);
+    }
+
+    /// Credit everything probation would have accrued, the day it ends.
+    fn end_probation(&mut self, date: NaiveDate) {
+        let Some(probation) = self.policy.probation else {
+            return;
+        };
+        if date != probation.end || !probation.retroactive || date <= self.today {
+            return;
+        }
+        let accrual: f32 = probation
+            .hire_date
+            .iter_days()
+            .take_while(|day| *day < probation.end)
+            .filter(|day| !self.policy.no_accrual.iter().any(|r| r.contains(*day)))
+            .flat_map(|day| self.policy.credits_on(day))
+            .map(|(_, hours)| hours)
+            .sum();
+        let room = self
+            .policy
+            .accrual_cap
+            .map_or(f32::INFINITY, |cap| (cap - self.balance).max(0.0));
+        let credited = accrual.min(room);
+        self.balance += credited;
+        self.record(date, EventKind::RetroactiveAccrual, credited);
+    }
+
+    /// Replace the balance with a known one, whatever the simulation got.
+    fn anchor(&mut self, date: NaiveDate, balance: f32) {
+        let correction = balance - self.balance;
+        self.balance = balance;
+        self.record(date, EventKind::BalanceAnchored, correction);
+    }
+
+    fn reset(&mut self, date: NaiveDate, reset: &BalanceReset) {
+        let change = reset.balance - self.balance;
+        self.balance = reset.balance;
+        let kind = EventKind::BalanceReset {
+            reason: reset.reason.clone(),
+        };
+        self.record(date, kind, change);
+    }
+
+    /// Deduct a vacation if the balance covers it.
+    fn deduct(&mut self, date: NaiveDate, vacation: &Vacation) -> Outcome {
+        let mut outcome = Outcome::new(vacation, self.policy, Status::Unaffordable, self.balance);
+        if vacation.unpaid {
+            outcome.status = Status::Unpaid;
+        } else if self
+            .policy
+            .max_pto_days_per_trip
+            .is_some_and(|max| outcome.days > max)
+        {
+            outcome.status = Status::TooLong;
+        } else if self.over_budget
+            || self
+                .policy
+                .budget
+                .is_some_and(|budget| self.planned + outcome.hours > budget)
+        {
+            self.over_budget = true;
+            outcome.status = Status::OverBudget;
+        } else if vacation.start == self.today
+            && self.policy.starting_today == StartingToday::InProgress
+        {
+            outcome.status = Status::Affordable;
+            self.planned += outcome.hours;
+        } else if self.balance >= outcome.hours {
+            outcome.status = Status::Affordable;
+            self.balance -= outcome.hours;
+            self.planned += outcome.hours;
+            let kind = EventKind::Deduction {
+                vacation: outcome.name.clone(),
+            };
+            self.record(date, kind, outcome.hours);
+        }
+        outcome
+    }
+
+    /// Credit whatever `credits_on` gives for `date`: the main accrual on its
+    /// frequency's days plus any accrual sources due, except while accrual is
+    /// suspended. The rate is looked up for each accrual date, so a vacation
+    /// spanning a rate change accrues at the old rate before it and the new
+    /// one after.
+    fn accrue(&mut self, date: NaiveDate) {
+        // Today's accrual is normally already in the bank
+        if date < self.today || (date == self.today && !self.policy.accrue_today) {
+            return;
+        }
+        let credits = self.policy.credits_on(date);
+        if credits.is_empty() {
+            return;
+        }
+        let accrual: f32 = credits.iter().map(|(_, hours)| hours).sum();
+        let fraction = self.policy.fraction_on(date);
+        match self.policy.probation {
+            // Retroactive accrual is added up when probation ends instead
+            Some(probation) if date < probation.end && probation.retroactive => return,
+            Some(probation) if date < probation.end => {
+                self.record(date, EventKind::AccrualSuspended, accrual);
+                return;
+            }
+            _ => {}
+        }
+        if self.policy.no_accrual.iter().any(|r| r.contains(date)) {
+            self.record(date, EventKind::AccrualSuspended, accrual);
+            return;
+        }
+
+        let room = self
+            .policy
+            .accrual_cap
+            .map_or(f32::INFINITY, |cap| (cap - self.balance).max(0.0));
+        let credited = accrual.min(room);
+        self.balance += credited;
+        let during_vacation = self
+            .vacations
+            .iter()
+            .any(|v| v.start <= date && date <= v.end);
+        self.record(
+            date,
+            EventKind::Accrual {
+                during_vacation,
+                fraction,
+                // The cap applies to the total, so this is before capping
+                sources: if self.policy.accrual_sources.is_empty() {
+                    Vec::new()
+                } else {
+                    credits
+                },
+            },
+            credited,
+        );
+
+        let excess = accrual - credited;
+        if excess > 0.0 {
+            let queued = match self.policy.cap_overflow {
+                CapOverflowMode::Drop => None,
+                CapOverflowMode::Queue => {
+                    self.queued += excess;
+                    Some(self.queued)
+                }
+            };
+            self.record(date, EventKind::AccrualCapped { queued }, excess);
+        }
+    }
+
+    /// Credit queued accrual once spending has made room under the cap.
+    fn release_queued(&mut self, date: NaiveDate) {
+        let Some(cap) = self.policy.accrual_cap else {
+            return;
+        };
+        let released = self.queued.min((cap - self.balance).max(0.0));
+        if released > 0.0 {
+            self.balance += released;
+            self.queued -= released;
+            let queued = self.queued;
+            self.record(date, EventKind::QueueReleased { queued }, released);
+        }
+    }
+}
+
+/// The error for a date past the furthest the simulation will go, or past
+/// the end of the calendar.
+pub fn too_far_out(date: impl std::fmt::Display) -> String {
+    format!(
+        "{} is more than {} years out; check the dates in your schedule and config",
+        date, MAX_YEARS_AHEAD
+    )
+}
+
+/// Walk forward one day at a time from `today`, applying carryover
+/// forfeiture, transfers, clawbacks, vacation deductions, release of queued
+/// accrual and accrual credits in that order on each day. Accrual starts on
+/// the first accrual day after today, or today with `accrue_today`.
+/// Vacations that already started are deducted on `today`. `vacations` must
+/// be sorted.
+///
+/// A vacation starting on an accrual day is deducted before that day's
+/// accrual is credited, unless `accrual_before_deduction` is set.
+///
+/// The final balance is taken once the last vacation, transfer or clawback is
+/// done. With a carryover cap the walk continues to the end of the current
+/// year so its closing balance is known. Nothing is simulated past
+/// `employment_end`; vacations starting after it are marked as such.
+///
+/// Stops at `max_horizon` if anything reaches past it. Otherwise fails if
+/// anything needs simulating more than `MAX_YEARS_AHEAD` years out,
+/// which is almost certainly a typo in a date.
+pub fn simulate(
+    today: NaiveDate,
+    vacations: &[Vacation],
+    policy: &Policy,
+) -> Result<Simulation, String> {
+    let mut run = Run {
+        today,
+        vacations,
+        policy,
+        balance: policy.bank,
+        queued: 0.0,
+        planned: 0.0,
+        over_budget: false,
+        other_banks: policy.other_banks.clone(),
+        events: Vec::new(),
+    };
+    let mut outcomes = Vec::new();
+
+    // Past transfers and clawbacks are already reflected in the current balances
+    let mut transfers: Vec<&Transfer> = policy
+        .transfers
+        .iter()
+        .filter(|t| t.date >= today)
+        .collect();
+    transfers.sort_by_key(|t| t.date);
+    let mut transfers = transfers.into_iter().peekable();
+    let mut clawbacks: Vec<&Clawback> = policy
+        .clawbacks
+        .iter()
+        .filter(|c| c.date >= today)
+        .collect();
+    clawbacks.sort_by_key(|c| c.date);
+    let mut clawbacks = clawbacks.into_iter().peekable();
+    let mut pending = vacations.iter().peekable();
+
+    let horizon = vacations
+        .iter()
+        .map(|v| v.end)
+        .chain(policy.transfers.iter().map(|t| t.date))
+        .chain(policy.clawbacks.iter().map(|c| c.date))
+        .chain(policy.anchors.iter().map(|a| a.date))
+        .chain(policy.resets.iter().map(|r| r.date))
+        .max()
+        .unwrap_or(today);
+    let mut until = horizon;
+    if policy.carryover_cap.is_some() || policy.cash_out.is_some() {
+        until = until.max(NaiveDate::from_ymd_opt(today.year(), 12, 31).unwrap());
+    }
+    if policy.carryover_cap.is_some()
+        && let Some(year_end) = policy.carryover_year_end(today)
+    {
+        until = until.max(year_end);
+    }
+    if let Some(project_until) = policy.project_until {
+        until = until.max(project_until);
+    }
+    // Nothing accrues or gets spent once employment ends
+    let horizon = policy
+        .employment_end
+        .map_or(horizon, |end| horizon.min(end));
+    let until = policy.employment_end.map_or(until, |end| until.min(end));
+    let truncated_at = policy.max_horizon.filter(|cap| until > *cap);
+    let horizon = truncated_at.map_or(horizon, |cap| horizon.min(cap));
+    let until = truncated_at.unwrap_or(until);
+    let limit = today
+        .with_year(today.year() + MAX_YEARS_AHEAD)
+        .or_else(|| NaiveDate::from_ymd_opt(today.year() + MAX_YEARS_AHEAD, 2, 28))
+        .unwrap_or(NaiveDate::MAX);
+    if until > limit {
+        return Err(too_far_out(until));
+    }
+    let mut final_balance = run.balance;
+    let mut year_ends = Vec::new();
+
+    let mut date = today;
+    while date <= until {
+        // Later anchors on the same date win
+        if let Some(anchor) = policy.anchors.iter().rev().find(|a| a.date == date) {
+            run.anchor(date, anchor.balance);
+        }
+        for reset in policy.resets.iter().filter(|r| r.date == date) {
+            run.reset(date, reset);
+        }
+        if let Some(change) = policy.rate_changes.iter().rev().find(|c| c.date == date) {
+            run.record(date, EventKind::RateChange, change.rate);
+        }
+        run.forfeit(date);
+        run.grant(date);
+        while let Some(transfer) = transfers.next_if(|t| t.date == date) {
+            run.transfer(date, transfer);
+        }
+        while let Some(clawback) = clawbacks.next_if(|c| c.date == date) {
+            run.claw_back(date, clawback);
+        }
+        run.end_probation(date);
+        if policy.accrual_before_deduction {
+            run.accrue(date);
+        }
+        while let Some(vacation) = pending.next_if(|v| v.start <= date) {
+            outcomes.push(run.deduct(date, vacation));
+        }
+        run.release_queued(date);
+        if !policy.accrual_before_deduction {
+            run.accrue(date);
+        }
+        run.cash_out(date);
+
+        if date == horizon {
+            final_balance = run.balance;
+        }
+        if date.month() == 12 && date.day() == 31 {
+            year_ends.push(YearEnd {
+                year: date.year(),
+                balance: run.balance,
+            });
+        }
+        match date.succ_opt() {
+            Some(next) => date = next,
+            None => break,
+        }
+    }
+    outcomes.extend(pending.map(|v| Outcome::new(v, policy, Status::AfterEmployment, run.balance)));
+
+    let mut simulation = Simulation {
+        today,
+        opening_balance: policy.bank,
+        outcomes,
+        events: run.events,
+        final_balance,
+        year_ends,
+        other_banks: run.other_banks,
+        truncated_at,
+    };
+    let per_trip: Vec<(f32, (usize, f32))> = simulation
+        .outcomes
+        .iter()
+        .map(|o| {
+            let (start, end) = (o.vacation.start, o.vacation.end);
+            (
+                simulation.balance_on(end),
+                simulation.accruals_in(|date| start <= date && date <= end),
+            )
+        })
+        .collect();
+    for (outcome, (at_return, accrued_during)) in simulation.outcomes.iter_mut().zip(per_trip) {
+        outcome.at_return = at_return;
+        outcome.accrued_during = accrued_during;
+    }
+    Ok(simulation)
+}
+
+/// The day a vacation from its `start` must end on to cost exactly `days`
+/// full PTO days, by its category's rules. `None` for zero days, or if
+/// there aren't that many business days in reach.
+pub fn business_days_end(vacation: &Vacation, days: u32, policy: &Policy) -> Option<NaiveDate> {
+    // Every week has a business day unless a category rules them all out;
+    // a year's slack covers the holidays
+    let longest = 7 * u64::from(days) + 366;
+    let window = Vacation {
+        end: vacation
+            .start
+            .checked_add_days(chrono::Days::new(longest))?,
+        fraction: None,
+        ..vacation.clone()
+    };
+    pto_dates(&window, policy).nth(days.checked_sub(1)? as usize)
+}
+
+/// Longest a flexible trip may be put off, in days after `today`.
+const FLEXIBLE_SEARCH_DAYS: u64 = 2 * 366;
+
+/// `vacation` moved to the earliest start, within two years, on which it's
+/// affordable alongside the fixed `vacations`, lasting `length` PTO days.
+/// Starts are only tried on days that cost PTO, so the trip never opens on a
+/// weekend. `None` if no start in that window works.
+pub fn earliest_start(
+    today: NaiveDate,
+    vacations: &[Vacation],
+    vacation: &Vacation,
+    length: f32,
+    policy: &Policy,
+) -> Option<Vacation> {
+    let length = length.ceil().max(1.0) as usize;
+    let window = Vacation {
+        start: today.succ_opt()?,
+        end: today + chrono::Days::new(FLEXIBLE_SEARCH_DAYS),
+        ..vacation.clone()
+    };
+    let candidates: Vec<NaiveDate> = pto_dates(&window, policy).collect();
+    candidates.windows(length).find_map(|days| {
+        let candidate = Vacation {
+            start: days[0],
+            end: days[length - 1],
+            ..window.clone()
+        };
+        // Outcomes come out in schedule order, so the candidate's is at the
+        // index it sorts to
+        let index = vacations.partition_point(|v| v.sort_key() <= candidate.sort_key());
+        let mut plan = vacations.to_vec();
+        plan.insert(index, candidate.clone());
+        let simulation = simulate(today, &plan, policy).ok()?;
+        simulation
+            .outcomes
+            .get(index)
+            .filter(|o| o.affordable())
+            .map(|_| candidate)
+    })
+}
+
+/// The lowest constant weekly accrual rate, to within a hundredth of an
+/// hour, at which no trip is unaffordable. Rate changes are ignored. `None`
+/// if no rate helps, e.g. when a trip comes before the first accrual.
+pub fn break_even_rate(today: NaiveDate, vacations: &[Vacation], policy: &Policy) -> Option<f32> {
+    let covers = |rate: f32| {
+        let policy = Policy {
+            accrual_per_week: rate,
+            rate_changes: Vec::new(),
+            ..policy.clone()
+        };
+        simulate(today, vacations, &policy).is_ok_and(|s| !s.has_shortfall())
+    };
+
+    // A week's accrual that pays for the whole plan is always enough if
+    // anything is
+    let mut high: f32 = vacations
+        .iter()
+        .map(|v| pto_days(v, policy) * HOURS_PER_DAY)
+        .sum::<f32>()
+        .max(1.0);
+    if !covers(high) {
+        return None;
+    }
+    let mut low = 0.0;
+    if covers(low) {
+        return Some(0.0);
+    }
+    while high - low > 0.01 {
+        let mid = (low + high) / 2.0;
+        if covers(mid) {
+            high = mid;
+        } else {
+            low = mid;
+        }
+    }
+    Some(high)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Wednesday; the next accrual Sunday is 2026-10-18.
+    const TODAY: &str = "2026-10-14";
+
+    fn day(date: &str) -> NaiveDate {
+        date.parse().unwrap()
+    }
+
+    fn trip(start: &str, end: &str) -> Vacation {
+        toml::from_str(&format!(
+            "start = {}\nend = {}\nname = \"Trip\"",
+            start, end
+        ))
+        .unwrap()
+    }
+
+    fn policy(bank: f32, accrual_per_week: f32) -> Policy {
+        Policy {
+            bank,
+            accrual_per_week,
+            ..Policy::default()
+        }
+    }
+
+    fn status(vacations: &[Vacation], policy: &Policy) -> Status {
+        simulate(day(TODAY), vacations, policy).unwrap().outcomes[0].status
+    }
+
+    #[test]
+    fn business_days_are_found_in_the_given_direction() {
+        // Saturday to Sunday: forward from the start, back from the end
+        let policy = Policy::default();
+        assert_eq!(
+            policy.next_business_day(day("2026-10-17"), true),
+            day("2026-10-19")
+        );
+        assert_eq!(
+            policy.next_business_day(day("2026-10-18"), false),
+            day("2026-10-16")
+        );
+        assert_eq!(
+            policy.next_business_day(day("2026-10-14"), true),
+            day("2026-10-14")
+        );
+    }
+
+    #[test]
+    fn accrual_on_start_sunday_follows_deduction_by_default() {
+        // Sunday to Friday costs five days; the bank is one accrual short
+        let vacations = [trip("2026-10-18", "2026-10-23")];
+        let policy = policy(36.0, 4.0);
+        assert_eq!(status(&vacations, &policy), Status::Unaffordable);
+    }
+
+    #[test]
+    fn accrual_on_start_sunday_can_come_first() {
+        let vacations = [trip("2026-10-18", "2026-10-23")];
+        let policy = Policy {
+            accrual_before_deduction: true,
+            ..policy(36.0, 4.0)
+        };
+        assert_eq!(status(&vacations, &policy), Status::Affordable);
+    }
+
+    #[test]
+    fn rate_change_inside_a_trip_applies_from_its_date() {
+        let vacations = [trip("2026-11-02", "2026-11-20")];
+        let policy = Policy {
+            rate_changes: vec![RateChange {
+                date: day("2026-11-10"),
+                rate: 10.0,
+            }],
+            ..policy(200.0, 4.0)
+        };
+        let simulation = simulate(day(TODAY), &vacations, &policy).unwrap();
+        let during: Vec<(NaiveDate, f32)> = simulation
+            .events
+            .iter()
+            .filter(|e| {
+                matches!(
+                    e.kind,
+                    EventKind::Accrual {
+                        during_vacation: true,
+                        ..
+                    }
+                )
+            })
+            .map(|e| (e.date, e.hours))
+            .collect();
+        assert_eq!(
+            during,
+            [(day("2026-11-08"), 4.0), (day("2026-11-15"), 10.0)]
+        );
+        assert_eq!(simulation.outcomes[0].accrued_during, (2, 14.0));
+    }
+
+    #[test]
+    fn far_future_trip_is_an_error() {
+        let vacations = [trip("2200-06-01", "2200-06-05")];
+        let result = simulate(day(TODAY), &vacations, &policy(40.0, 4.0));
+        assert!(result.is_err_and(|e| e.contains("years out")));
+    }
+
+    #[test]
+    fn max_horizon_past_the_guard_still_errors() {
+        // As with maxHorizonYears = 150: the cap alone doesn't stop the walk
+        let vacations = [trip("2150-06-01", "2150-06-05")];
+        let policy = Policy {
+            max_horizon: Some(day("2176-10-14")),
+            ..policy(40.0, 4.0)
+        };
+        assert!(simulate(day(TODAY), &vacations, &policy).is_err());
+    }
+
+    #[test]
+    fn max_horizon_truncates() {
+        let vacations = [trip("2026-11-09", "2026-11-13")];
+        let policy = Policy {
+            max_horizon: Some(day("2027-10-14")),
+            project_until: Some(day("2030-01-01")),
+            ..policy(40.0, 4.0)
+        };
+        let simulation = simulate(day(TODAY), &vacations, &policy).unwrap();
+        assert_eq!(simulation.truncated_at, Some(day("2027-10-14")));
+        assert!(
+            simulation
+                .events
+                .iter()
+                .all(|e| e.date <= day("2027-10-14"))
+        );
+    }
+
+    #[test]
+    fn zero_accrual_never_affords_a_trip_beyond_the_bank() {
+        let vacations = [trip("2027-06-07", "2027-06-11")];
+        let policy = policy(8.0, 0.0);
+        let simulation = simulate(day(TODAY), &vacations, &policy).unwrap();
+        assert_eq!(simulation.outcomes[0].status, Status::Unaffordable);
+        assert!(
+            simulation
+                .events
+                .iter()
+                .all(|e| !matches!(e.kind, EventKind::Accrual { .. }))
+        );
+        assert_eq!(policy.credits_to_earn(day("2027-06-07"), 32.0), None);
+    }
+
+    #[test]
+    fn break_even_rate_is_finite_from_zero_accrual() {
+        let vacations = [trip("2027-06-07", "2027-06-11")];
+        let rate = break_even_rate(day(TODAY), &vacations, &policy(8.0, 0.0)).unwrap();
+        assert!(rate.is_finite() && rate > 0.0);
+
+        // Nothing accrues before a trip starting tomorrow
+        let vacations = [trip("2026-10-15", "2026-10-16")];
+        assert_eq!(
+            break_even_rate(day(TODAY), &vacations, &policy(8.0, 0.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn trip_starting_today_is_deducted_by_default() {
+        let vacations = [trip(TODAY, "2026-10-16")];
+        let simulation = simulate(day(TODAY), &vacations, &policy(24.0, 4.0)).unwrap();
+        assert_eq!(simulation.outcomes[0].status, Status::Affordable);
+        assert!(
+            simulation
+                .events
+                .iter()
+                .any(|e| matches!(e.kind, EventKind::Deduction { .. }) && e.balance == 0.0)
+        );
+    }
+
+    #[test]
+    fn trip_in_progress_today_is_already_paid() {
+        let vacations = [trip(TODAY, "2026-10-16")];
+        let policy = Policy {
+            starting_today: StartingToday::InProgress,
+            ..policy(0.0, 4.0)
+        };
+        let simulation = simulate(day(TODAY), &vacations, &policy).unwrap();
+        assert_eq!(simulation.outcomes[0].status, Status::Affordable);
+        assert!(
+            simulation
+                .events
+                .iter()
+                .all(|e| !matches!(e.kind, EventKind::Deduction { .. }))
+        );
+    }
+
+    #[test]
+    fn accrual_landing_today_is_only_credited_on_request() {
+        // A Sunday: the bank is taken to include today's accrual already
+        let sunday = day("2026-10-18");
+        let vacations = [trip("2026-10-18", "2026-10-20")];
+        let base = Policy {
+            accrual_before_deduction: true,
+            ..policy(12.0, 4.0)
+        };
+        let status =
+            |policy: &Policy| simulate(sunday, &vacations, policy).unwrap().outcomes[0].status;
+        assert_eq!(status(&base), Status::Unaffordable);
+        let accrue_today = Policy {
+            accrue_today: true,
+            ..base.clone()
+        };
+        assert_eq!(status(&accrue_today), Status::Affordable);
+    }
+
+    /// Hours charged for a full week and for a half day, both over a half
+    /// holiday on Wednesday 2026-11-11.
+    fn half_holiday_hours(rule: PartialHolidayRule) -> (f32, f32) {
+        let policy = Policy {
+            partial_holidays: BTreeMap::from([(day("2026-11-11"), 0.5)]),
+            partial_holiday_rule: rule,
+            ..policy(80.0, 4.0)
+        };
+        let week = trip("2026-11-09", "2026-11-13");
+        let half_day = Vacation {
+            fraction: Some(0.5),
+            ..trip("2026-11-11", "2026-11-11")
+        };
+        let hours = |v: &Vacation| pto_days(v, &policy) * HOURS_PER_DAY;
+        (hours(&week), hours(&half_day))
+    }
+
+    #[test]
+    fn half_holiday_subtracts_from_the_day() {
+        assert_eq!(
+            half_holiday_hours(PartialHolidayRule::Subtract),
+            (36.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn half_holiday_scales_the_day() {
+        assert_eq!(half_holiday_hours(PartialHolidayRule::Scale), (36.0, 2.0));
+    }
+
+    #[test]
+    fn half_holiday_can_free_the_day() {
+        assert_eq!(half_holiday_hours(PartialHolidayRule::Free), (32.0, 0.0));
+    }
+}
@@ -0,0 +1,78 @@
+//! Annual PTO statements reconstructed from the simulation, for
+//! `--fiscal-summary-csv`.
+
+use crate::csv;
+use crate::sim::{Ledger, Simulation};
+use chrono::NaiveDate;
+
+const HEADER: &[&str] = &[
+    "year",
+    "from",
+    "to",
+    "opening",
+    "accrued",
+    "used",
+    "forfeited",
+    "cashed_out",
+    "transferred",
+    "adjusted",
+    "closing",
+];
+
+/// One row per calendar year and a totals row, in hours to two decimal
+/// places. `used` includes clawbacks; `adjusted` is balance anchor
+/// corrections and balance resets.
+pub fn csv(simulation: &Simulation) -> String {
+    let years = simulation.yearly_ledgers();
+    let mut out = csv::row(HEADER);
+    for (year, ledger) in &years {
+        let from = NaiveDate::from_ymd_opt(*year, 1, 1)
+            .unwrap()
+            .max(simulation.today);
+        let to = NaiveDate::from_ymd_opt(*year, 12, 31).unwrap();
+        let to = match simulation.events.last() {
+            Some(last) if last.date < to => last.date,
+            _ => to,
+        };
+        out += &row(&year.to_string(), from, to, ledger);
+    }
+    if let (Some((_, first)), Some((_, last))) = (years.first(), years.last()) {
+        let total = years.iter().fold(
+            Ledger {
+                opening: first.opening,
+                closing: last.closing,
+                ..Ledger::default()
+            },
+            |mut total, (_, ledger)| {
+                total.accrued += ledger.accrued;
+                total.deducted += ledger.deducted;
+                total.forfeited += ledger.forfeited;
+                total.cashed_out += ledger.cashed_out;
+                total.transferred += ledger.transferred;
+                total.anchored += ledger.anchored;
+                total
+            },
+        );
+        let from = simulation.today;
+        let to = simulation.events.last().map_or(from, |e| e.date);
+        out += &row("total", from, to, &total);
+    }
+    out
+}
+
+fn row(label: &str, from: NaiveDate, to: NaiveDate, ledger: &Ledger) -> String {
+    let hours = [
+        ledger.opening,
+        ledger.accrued,
+        ledger.deducted,
+        ledger.forfeited,
+        ledger.cashed_out,
+        ledger.transferred,
+        ledger.anchored,
+        ledger.closing,
+    ]
+    .map(|h| format!("{:.2}", h + 0.0));
+    let mut fields = vec![label.to_string(), from.to_string(), to.to_string()];
+    fields.extend(hours);
+    csv::row(&fields)
+}
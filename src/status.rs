@@ -0,0 +1,58 @@
+//! The machine-readable summary of a run, shared by `--status-file` and the
+//! webhook payload.
+
+use crate::json;
+use crate::sim::{EventKind, Policy, Simulation};
+use chrono::{Datelike, NaiveDate};
+
+/// The first day hours over the carryover cap are lost: a forfeiture the
+/// simulation reached, else the start of the next carryover year when the
+/// balance closing this one is over the cap.
+pub fn soonest_forfeiture(simulation: &Simulation, policy: &Policy) -> Option<NaiveDate> {
+    let forfeited = simulation
+        .events
+        .iter()
+        .find(|e| e.kind == EventKind::Forfeiture && e.hours > 0.0)
+        .map(|e| e.date);
+    forfeited.or_else(|| {
+        let cap = policy.carryover_cap?;
+        let today = simulation.today;
+        let year_end = policy
+            .carryover_year_end(today)
+            .or_else(|| NaiveDate::from_ymd_opt(today.year(), 12, 31))?;
+        (simulation.balance_on(year_end) > cap)
+            .then(|| year_end.succ_opt())
+            .flatten()
+    })
+}
+
+/// `unaffordable` counts every trip that doesn't go ahead as planned, so it
+/// and `affordable` add up to the trips in the plan. `shortfall_count` is the
+/// same number under the name the first webhook payloads used; keep it for
+/// receivers that read it.
+pub fn summary(simulation: &Simulation, policy: &Policy) -> String {
+    let shortfalls: Vec<_> = simulation
+        .outcomes
+        .iter()
+        .filter(|o| !o.affordable())
+        .collect();
+    let soonest = shortfalls.first().map(|o| o.vacation.start.to_string());
+    json::Object::new()
+        .bool("ok", simulation.is_ok())
+        .int("affordable", simulation.outcomes.len() - shortfalls.len())
+        .int("unaffordable", shortfalls.len())
+        .int("shortfall_count", shortfalls.len())
+        .num("final_balance", simulation.final_balance)
+        .opt_str("soonest_shortfall", soonest.as_deref())
+        .opt_str(
+            "soonest_shortfall_vacation",
+            shortfalls.first().map(|o| o.name.as_str()),
+        )
+        .opt_str(
+            "soonest_forfeiture",
+            soonest_forfeiture(simulation, policy)
+                .map(|d| d.to_string())
+                .as_deref(),
+        )
+        .build()
+}
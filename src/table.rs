@@ -0,0 +1,274 @@
+use crate::locale::{Balance, Num};
+use crate::sim::{Outcome, Simulation};
+use chrono::{Datelike, NaiveDate};
+use clap::ValueEnum;
+use color_print::cformat;
+use tabled::Table;
+use tabled::builder::Builder;
+use tabled::settings::Span;
+
+/// A column of the vacation table, selectable with `--columns`.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Column {
+    #[value(alias = "name")]
+    Vacation,
+    Start,
+    End,
+    Days,
+    Hours,
+    Status,
+    Booked,
+    Category,
+    Who,
+    /// Balance once the trip is over, after accrual during it.
+    #[value(alias = "balance-at-return")]
+    Return,
+    /// Accrual credited during the trip, which offsets part of its cost.
+    #[value(alias = "accrued-during")]
+    Accrued,
+    /// Hours of pay an unpaid trip costs, and their value at `hourlyRate`.
+    #[value(alias = "unpaid-cost")]
+    Unpaid,
+}
+
+/// How cells are drawn.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Style {
+    pub icons: bool,
+    /// Show dates like "Mon, Jul 1 (in 3 weeks)", counting from this day.
+    pub pretty_dates_from: Option<NaiveDate>,
+}
+
+impl Style {
+    fn date(self, date: NaiveDate) -> String {
+        let Some(today) = self.pretty_dates_from else {
+            return date.to_string();
+        };
+        let format = if date.year() == today.year() {
+            "%a, %b %-d"
+        } else {
+            "%a, %b %-d %Y"
+        };
+        format!("{} ({})", date.format(format), relative(today, date))
+    }
+}
+
+/// How far `date` is from `today` in rough human terms.
+fn relative(today: NaiveDate, date: NaiveDate) -> String {
+    let days = (date - today).num_days();
+    let (count, unit) = match days.abs() {
+        0 => return "today".to_string(),
+        1 if days > 0 => return "tomorrow".to_string(),
+        1 => return "yesterday".to_string(),
+        n @ ..14 => (n, "day"),
+        n @ ..60 => (n / 7, "week"),
+        n @ ..730 => (n / 30, "month"),
+        n => (n / 365, "year"),
+    };
+    let plural = if count == 1 { "" } else { "s" };
+    if days > 0 {
+        format!("in {} {}{}", count, unit, plural)
+    } else {
+        format!("{} {}{} ago", count, unit, plural)
+    }
+}
+
+/// How `--group-by` splits the vacation table.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GroupBy {
+    Category,
+}
+
+/// Group name for vacations without a category.
+pub const UNCATEGORIZED: &str = "uncategorized";
+
+impl Column {
+    pub const DEFAULT: &[Column] = &[
+        Column::Vacation,
+        Column::Start,
+        Column::End,
+        Column::Days,
+        Column::Hours,
+        Column::Status,
+    ];
+
+    fn header(self) -> &'static str {
+        match self {
+            Column::Vacation => "Vacation",
+            Column::Start => "Start",
+            Column::End => "End",
+            Column::Days => "Days",
+            Column::Hours => "Hours",
+            Column::Status => "Status",
+            Column::Booked => "Booked",
+            Column::Category => "Category",
+            Column::Who => "Who",
+            Column::Return => "Balance at Return",
+            Column::Accrued => "Accrued During",
+            Column::Unpaid => "Unpaid Cost",
+        }
+    }
+
+    fn cell(self, outcome: &Outcome, style: Style) -> String {
+        match self {
+            Column::Vacation => match icon(outcome).filter(|_| style.icons) {
+                Some(icon) => format!("{} {}", icon, outcome.name),
+                None => outcome.name.clone(),
+            },
+            Column::Start if outcome.vacation.flexible_length.is_some() => {
+                format!("{} (suggested)", style.date(outcome.vacation.start))
+            }
+            Column::Start => style.date(outcome.vacation.start),
+            Column::End => style.date(outcome.vacation.end),
+            Column::Days => number(outcome.days),
+            Column::Hours => number(outcome.hours),
+            Column::Status => outcome.status.glyph().to_string(),
+            Column::Booked => outcome
+                .vacation
+                .booked
+                .map(|b| b.summary())
+                .unwrap_or_default(),
+            Column::Category => outcome.vacation.category.clone().unwrap_or_default(),
+            Column::Who => outcome.vacation.owner.clone().unwrap_or_default(),
+            Column::Accrued => match outcome.accrued_during {
+                (0, _) => String::new(),
+                (count, hours) => format!("+{} ({})", number(hours), count),
+            },
+            Column::Unpaid if outcome.vacation.unpaid => match outcome.lost_pay {
+                Some(pay) => format!("{} hours, worth {}", number(outcome.hours), number(pay)),
+                None => format!("{} hours", number(outcome.hours)),
+            },
+            Column::Unpaid => String::new(),
+            Column::Return => {
+                let balance = Balance(outcome.at_return);
+                format!("{}{}", number(balance.shown()), balance.owed())
+            }
+        }
+    }
+
+    /// The default columns, plus any optional ones the schedule has data for.
+    pub fn defaults(outcomes: &[Outcome]) -> Vec<Column> {
+        let mut columns = Column::DEFAULT.to_vec();
+        if outcomes.iter().any(|o| o.vacation.owner.is_some()) {
+            columns.insert(0, Column::Who);
+        }
+        if outcomes.iter().any(|o| o.vacation.booked.is_some()) {
+            columns.push(Column::Booked);
+        }
+        if outcomes.iter().any(|o| o.vacation.unpaid) {
+            columns.push(Column::Unpaid);
+        }
+        columns
+    }
+}
+
+/// Icons picked for trips tagged with these words.
+const TAG_ICONS: &[(&str, &str)] = &[
+    ("beach", "🏖️"),
+    ("conference", "🎤"),
+    ("ski", "⛷️"),
+    ("camping", "🏕️"),
+    ("family", "👪"),
+    ("wedding", "💒"),
+    ("sick", "🤒"),
+];
+
+/// The trip's own icon, else one for the first tag that has one.
+fn icon(outcome: &Outcome) -> Option<&str> {
+    outcome.vacation.icon.as_deref().or_else(|| {
+        outcome.vacation.tags.iter().find_map(|tag| {
+            TAG_ICONS
+                .iter()
+                .find(|(name, _)| tag.eq_ignore_ascii_case(name))
+                .map(|(_, icon)| *icon)
+        })
+    })
+}
+
+/// Up to two decimal places, without trailing zeros.
+fn number(value: f32) -> String {
+    // Adding zero turns the -0.0 an empty sum produces into 0.0
+    let formatted = format!("{:.2}", value + 0.0);
+    let trimmed: f32 = formatted
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .parse()
+        .unwrap_or(value);
+    Num(trimmed).to_string()
+}
+
+/// Split outcomes by category, in order of each category's first trip.
+pub fn by_category(outcomes: &[Outcome]) -> Vec<(&str, Vec<&Outcome>)> {
+    let mut groups: Vec<(&str, Vec<&Outcome>)> = Vec::new();
+    for outcome in outcomes {
+        let category = outcome
+            .vacation
+            .category
+            .as_deref()
+            .unwrap_or(UNCATEGORIZED);
+        match groups.iter_mut().find(|(name, _)| *name == category) {
+            Some((_, group)) => group.push(outcome),
+            None => groups.push((category, vec![outcome])),
+        }
+    }
+    groups
+}
+
+/// Total days and hours of a group of vacations, e.g. `3.5 days, 28 hours`.
+pub fn subtotal(outcomes: &[&Outcome]) -> String {
+    let days: f32 = outcomes.iter().map(|o| o.days).sum();
+    let hours: f32 = outcomes.iter().map(|o| o.hours).sum();
+    format!("{} days, {} hours", number(days), number(hours))
+}
+
+/// Build the vacation table with the given columns, in the given order.
+pub fn vacations<'a>(
+    outcomes: impl IntoIterator<Item = &'a Outcome>,
+    columns: &[Column],
+    style: Style,
+) -> Table {
+    let mut builder = Builder::default();
+    builder.push_record(columns.iter().map(|c| c.header()));
+    for outcome in outcomes {
+        builder.push_record(columns.iter().map(|c| c.cell(outcome, style)));
+    }
+    builder.build()
+}
+
+/// The vacation table with a dim line between consecutive trips summarizing
+/// the accrual in between.
+pub fn with_interim(simulation: &Simulation, columns: &[Column], style: Style) -> Table {
+    let mut builder = Builder::default();
+    builder.push_record(columns.iter().map(|c| c.header()));
+    let mut interim_rows = Vec::new();
+    let mut previous: Option<&Outcome> = None;
+    for outcome in &simulation.outcomes {
+        if let Some(previous) = previous {
+            let (before, before_hours) = simulation.accruals_before(previous.vacation.start);
+            let (until, until_hours) = simulation.accruals_before(outcome.vacation.start);
+            if until > before {
+                let balance = simulation.balance_on(outcome.vacation.start.pred_opt().unwrap());
+                let count = until - before;
+                let line = cformat!(
+                    "<dim>+ {} accrual{}, +{}h → balance {}h</dim>",
+                    count,
+                    if count == 1 { "" } else { "s" },
+                    number(until_hours - before_hours),
+                    number(Balance(balance).shown()) + &Balance(balance).owed()
+                );
+                interim_rows.push(builder.count_records());
+                builder.push_record(
+                    std::iter::once(line)
+                        .chain(std::iter::repeat_n(String::new(), columns.len() - 1)),
+                );
+            }
+        }
+        builder.push_record(columns.iter().map(|c| c.cell(outcome, style)));
+        previous = Some(outcome);
+    }
+    let mut table = builder.build();
+    for row in interim_rows {
+        table.modify((row, 0), Span::column(columns.len() as isize));
+    }
+    table
+}
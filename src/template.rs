@@ -0,0 +1,35 @@
+use chrono::{Datelike, Days, NaiveDate};
+use std::fmt::Write;
+
+/// Weeks between the placeholder trips in a plan template.
+const WEEKS_BETWEEN_TRIPS: u64 = 8;
+
+/// A commented schedule skeleton with `trips` placeholder vacations, each a
+/// Monday-to-Friday week, the first about a month out.
+pub fn plan(today: NaiveDate, trips: usize) -> String {
+    let mut out = String::from(
+        "# vacay schedule. Each [[vacations]] entry is one trip.\n\
+         #\n\
+         #   start  - first day off (YYYY-MM-DD)\n\
+         #   end    - last day off, inclusive\n\
+         #   name   - optional label shown in the table\n\
+         #   booked - optional checklist, e.g. { flights = true, hotel = false, approved = true }\n\
+         #\n\
+         # Weekends and configured holidays inside a trip don't cost PTO.\n",
+    );
+
+    // Start on the Monday of the week four weeks out
+    let mut start = today + Days::new(28);
+    start = start - Days::new(start.weekday().num_days_from_monday().into());
+    for trip in 1..=trips {
+        let end = start + Days::new(4);
+        write!(
+            out,
+            "\n[[vacations]]\nstart = {}\nend = {}\nname = \"Trip {}\"\n",
+            start, end, trip
+        )
+        .unwrap();
+        start = start + Days::new(WEEKS_BETWEEN_TRIPS * 7);
+    }
+    out
+}
@@ -0,0 +1,38 @@
+//! `--benchmark` phase timings.
+
+use color_print::ceprintln;
+use std::time::{Duration, Instant};
+
+/// Times consecutive phases of a run, each from the end of the last.
+pub struct Stopwatch {
+    last: Instant,
+    laps: Vec<(&'static str, Duration)>,
+}
+
+impl Stopwatch {
+    pub fn start() -> Self {
+        Stopwatch {
+            last: Instant::now(),
+            laps: Vec::new(),
+        }
+    }
+
+    /// Close the phase called `phase` and start the next.
+    pub fn lap(&mut self, phase: &'static str) {
+        let now = Instant::now();
+        self.laps.push((phase, now - self.last));
+        self.last = now;
+    }
+
+    /// Print each phase's time and the total to stderr.
+    pub fn report(&self) {
+        let total: Duration = self.laps.iter().map(|(_, time)| *time).sum();
+        for (phase, time) in self.laps.iter().chain([&("total", total)]) {
+            ceprintln!(
+                "<dim>{:<10} {:>10.3} ms</dim>",
+                phase,
+                time.as_secs_f64() * 1000.0
+            );
+        }
+    }
+}
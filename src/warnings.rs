@@ -0,0 +1,38 @@
+//! Where non-fatal warnings go: stderr by default, or the file given with
+//! `--warnings-file` so automated runs keep them out of the error stream.
+
+use color_print::ceprintln;
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+static FILE: OnceLock<Mutex<File>> = OnceLock::new();
+
+/// Send every later warning to `path`, replacing what it held.
+pub fn to_file(path: &Path) -> io::Result<()> {
+    let file = File::create(path)?;
+    let _ = FILE.set(Mutex::new(file));
+    Ok(())
+}
+
+/// Report something worth knowing that doesn't stop the run.
+pub fn warn(message: impl Display) {
+    line(format_args!("Warning: {}", message));
+}
+
+/// A further line belonging to the last warning.
+pub fn detail(message: impl Display) {
+    line(format_args!("  {}", message));
+}
+
+fn line(text: impl Display) {
+    match FILE.get() {
+        Some(file) => {
+            let mut file = file.lock().unwrap_or_else(|e| e.into_inner());
+            let _ = writeln!(file, "{}", text);
+        }
+        None => ceprintln!("<yellow>{}</yellow>", text),
+    }
+}